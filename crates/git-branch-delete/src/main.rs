@@ -1,10 +1,22 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
-use git_utils_core::git;
-use inquire::Confirm;
+use git_utils_core::config::{self, Config};
+use git_utils_core::git::{self, MergeStatus};
+use git_utils_core::term::{self, ColorChoice};
+use glob::Pattern;
+use inquire::ui::RenderConfig;
+use inquire::{Confirm, MultiSelect};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Parser)]
-#[command(name = "git-branch-delete")]
+#[command(name = "git-branch-delete", version)]
 #[command(about = "Delete git branches interactively", long_about = None)]
 struct Cli {
     /// Delete all branches except base and current
@@ -15,24 +27,164 @@ struct Cli {
     #[arg(short, long)]
     merged: bool,
 
-    /// Select branches one by one
+    /// Pick the branches to delete from a checklist (merged branches start checked)
     #[arg(short, long, conflicts_with = "all")]
     select: bool,
 
+    /// Delete branches merged into the given revision (e.g. a tag) instead of the base branch
+    #[arg(long, value_name = "REV", conflicts_with_all = ["all", "released"])]
+    merged_into: Option<String>,
+
+    /// Delete branches merged into the latest release tag
+    #[arg(long, conflicts_with = "all")]
+    released: bool,
+
+    /// Delete branches whose upstream was deleted on the remote (`[gone]` in
+    /// git branch -vv), merged or not; run `git fetch --prune` first
+    #[arg(long, conflicts_with_all = ["all", "merged"])]
+    gone: bool,
+
+    /// First remove origin's remote-tracking branches that were deleted on the
+    /// server, like `git remote prune origin` (combine with --gone)
+    #[arg(long)]
+    prune: bool,
+
     /// Force delete (use -D instead of -d)
     #[arg(short, long, conflicts_with = "merged")]
     force: bool,
+
+    /// Also delete the corresponding remote branch (asks for confirmation)
+    #[arg(short, long)]
+    remote: bool,
+
+    /// With --remote, delete remote branches without asking
+    #[arg(long, requires = "remote")]
+    no_remote_prompt: bool,
+
+    /// When to use colors: auto, always, never
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Only delete branches whose last commit is older than DURATION (e.g. 30d, 6w)
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    older_than: Option<Duration>,
+
+    /// Keep the N most recently used branches (by reflog) regardless of merge status
+    #[arg(long, value_name = "N")]
+    keep: Option<usize>,
+
+    /// Show what would be deleted (including remote branches) without deleting anything
+    /// or prompting (with --select, lists the branches that would be offered)
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+
+    /// Only consider branches whose full name matches the glob pattern, e.g.
+    /// 'dependabot/*' (can be repeated; `*` also matches `/`)
+    #[arg(short, long, value_name = "PATTERN")]
+    pattern: Vec<String>,
+
+    /// Never delete branches matching the glob pattern, even with --all or --force
+    /// (can be repeated; adds to git-branch-delete.protected)
+    #[arg(long, value_name = "PATTERN")]
+    protect: Vec<String>,
+
+    /// Show which branches were protected or pruned
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Exclude branches matching the glob pattern (can be repeated)
+    #[arg(short = 'x', long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// Append a JSON Lines audit record of deleted branches to this file
+    #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+    report: Option<PathBuf>,
+}
+
+/// One `--report` line, written per run that deletes branches
+#[derive(Serialize)]
+struct Report {
+    timestamp: String,
+    repo: String,
+    base: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merge_target: Option<String>,
+    deleted: Vec<DeletedBranch>,
 }
 
-fn main() -> Result<()> {
+#[derive(Serialize)]
+struct DeletedBranch {
+    name: String,
+    tip: String,
+    merged: bool,
+    remote_deleted: bool,
+}
+
+fn main() -> ExitCode {
+    term::exit_code(run())
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse();
 
+    if !term::use_color(cli.color) {
+        inquire::set_global_render_config(RenderConfig::empty());
+    }
+
+    let include_patterns = cli
+        .pattern
+        .iter()
+        .map(|p| Pattern::new(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let exclude_patterns = cli
+        .exclude
+        .iter()
+        .map(|p| Pattern::new(p))
+        .collect::<Result<Vec<_>, _>>()?;
+
     let repo = git::open_repo()?;
+    let config = Config::load_for_repo(&repo)?;
+    let protected_patterns = config
+        .get_list("git-branch-delete.protected")
+        .iter()
+        .chain(&cli.protect)
+        .map(|p| Pattern::new(p))
+        .collect::<Result<Vec<_>, _>>()?;
     let current_branch = git::get_current_branch(&repo)?;
     let base_branch = git::detect_base_branch(&repo)?;
+    let strategy = git::merge_strategy(&repo)?;
+
+    // Revision that branches must be merged into (base branch unless overridden)
+    let merge_target = if let Some(rev) = &cli.merged_into {
+        rev.clone()
+    } else if cli.released {
+        git::get_latest_tag(&repo).ok_or_else(|| anyhow!("No tags found for --released"))?
+    } else {
+        base_branch.clone()
+    };
+
+    // Prune first so branches whose upstream was just pruned show up as --gone
+    if cli.prune {
+        let pruned = git::prune_remote(&repo, "origin", cli.dry_run)?;
+        let verb = if cli.dry_run { "Would prune" } else { "Pruned" };
+        if cli.verbose {
+            for name in &pruned {
+                eprintln!("{} {}", verb, name);
+            }
+        }
+        println!(
+            "{} {} stale remote-tracking branch{} from origin",
+            verb,
+            pruned.len(),
+            if pruned.len() == 1 { "" } else { "es" }
+        );
+    }
 
     println!("Base branch: {}", base_branch);
     println!("Current branch: {}", current_branch);
+    if merge_target != base_branch {
+        println!("Merge target: {}", merge_target);
+    }
 
     // Get all local branches
     let mut branches = git::get_local_branches(&repo)?;
@@ -40,9 +192,72 @@ fn main() -> Result<()> {
     // Remove current and base branches
     branches.retain(|b| b != &current_branch && b != &base_branch);
 
-    // Filter by merge status (default is merged unless --force)
-    if !cli.all && !cli.force {
-        branches.retain(|b| git::is_branch_merged(&repo, b, &base_branch).unwrap_or_default());
+    // Protected branches are never candidates, whatever the other flags say
+    let (protected, candidates): (Vec<String>, Vec<String>) = branches
+        .into_iter()
+        .partition(|b| protected_patterns.iter().any(|p| p.matches(b)));
+    branches = candidates;
+    if cli.verbose && !protected.is_empty() {
+        eprintln!("Protected: {}", protected.join(", "));
+    }
+
+    // Narrow to --pattern matches before checking merge status
+    if !include_patterns.is_empty() {
+        branches.retain(|b| include_patterns.iter().any(|p| p.matches(b)));
+    }
+
+    // Merged per the merge strategy, or squash-merged; each branch is checked
+    // once, as the listing, prompt and delete loop all need the status
+    let checker = git::MergeChecker::new(&repo, &merge_target, strategy)?;
+    let statuses = RefCell::new(HashMap::new());
+    let status = |branch: &str| {
+        *statuses
+            .borrow_mut()
+            .entry(branch.to_string())
+            .or_insert_with(|| checker.status(branch).unwrap_or(MergeStatus::Unmerged))
+    };
+
+    // The server considers gone branches done, so they skip the merge check
+    if cli.gone {
+        branches.retain(|b| git::is_upstream_gone(&repo, b).unwrap_or(false));
+    } else if !cli.all && !cli.force {
+        // Filter by merge status (default is merged unless --force)
+        branches.retain(|b| status(b).is_merged());
+    }
+
+    // Age of each branch's last commit, in seconds
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let age = |branch: &str| {
+        git::get_branch_commit_time(&repo, branch)
+            .ok()
+            .map(|time| (now - time).max(0))
+    };
+
+    // Keep only stale branches (composes with the merged filter above)
+    if let Some(older_than) = cli.older_than {
+        let min_age = older_than.as_secs() as i64;
+        branches.retain(|b| age(b).is_some_and(|a| a >= min_age));
+    }
+
+    // Remove branches matching --exclude patterns
+    if !exclude_patterns.is_empty() {
+        branches.retain(|b| !exclude_patterns.iter().any(|p| p.matches(b)));
+    }
+
+    // Protect the most recently used branches
+    if let Some(keep) = cli.keep {
+        let kept: Vec<String> = git::get_recent_branches(&repo, None)?
+            .into_iter()
+            .filter(|b| branches.contains(b))
+            .take(keep)
+            .collect();
+        if !kept.is_empty() {
+            println!("Keeping recently used: {}", kept.join(", "));
+        }
+        branches.retain(|b| !kept.contains(b));
     }
 
     if branches.is_empty() {
@@ -50,36 +265,47 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if cli.dry_run {
+        print_dry_run(&repo, &branches, cli.remote, |branch| {
+            branch_annotations(status(branch), age(branch))
+        });
+        return Ok(());
+    }
+
     // Select mode
     let branches_to_delete = if cli.select {
-        let mut selected = Vec::new();
-        for branch in &branches {
-            let is_merged = git::is_branch_merged(&repo, branch, &base_branch).unwrap_or(false);
-            let label = if is_merged {
-                format!("{} [merged]", branch)
-            } else {
-                branch.clone()
-            };
-
-            let answer = Confirm::new(&format!("Delete branch '{}'?", label))
-                .with_default(false)
-                .prompt()?;
+        let statuses: Vec<MergeStatus> = branches.iter().map(|b| status(b)).collect();
+        let labels: Vec<String> = branches
+            .iter()
+            .zip(&statuses)
+            .map(|(branch, &status)| {
+                format!("{}{}", branch, branch_annotations(status, age(branch)))
+            })
+            .collect();
+        // Merged branches start checked
+        let merged: Vec<usize> = statuses
+            .iter()
+            .enumerate()
+            .filter(|(_, status)| status.is_merged())
+            .map(|(i, _)| i)
+            .collect();
 
-            if answer {
-                selected.push(branch.clone());
-            }
-        }
-        selected
+        MultiSelect::new("Branches to delete:", labels)
+            .with_default(&merged)
+            .with_help_message("space to toggle, → all, ← none, enter to delete")
+            .raw_prompt()?
+            .into_iter()
+            .map(|option| branches[option.index].clone())
+            .collect()
     } else {
         // Show branches to be deleted
         println!("\nBranches to be deleted:");
         for branch in &branches {
-            let is_merged = git::is_branch_merged(&repo, branch, &base_branch).unwrap_or(false);
-            if is_merged {
-                println!("  {} [merged]", branch);
-            } else {
-                println!("  {}", branch);
-            }
+            println!(
+                "  {}{}",
+                branch,
+                branch_annotations(status(branch), age(branch))
+            );
         }
 
         let answer = Confirm::new(&format!("\nDelete {} branches?", branches.len()))
@@ -99,30 +325,271 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    let mut deleted_count = 0;
-    let mut skipped_count = 0;
+    let repo_root = git::get_repo_root(&repo)?
+        .components()
+        .collect::<PathBuf>()
+        .display()
+        .to_string();
+    let mut done = Deletion::default();
+    // Merge status was already checked above (against a custom target, too),
+    // and gone branches are deleted regardless
+    let force = cli.force || cli.gone || merge_target != base_branch;
+    // Logging to ~/.git-utils/deleted-branches.log is on unless git-branch-delete.log = false
+    let log_as = (config.get_bool("git-branch-delete.log") != Some(false)).then_some(&*repo_root);
+    let outcome = delete_branches(
+        &cli,
+        &repo,
+        &branches_to_delete,
+        log_as,
+        force,
+        |b| status(b).is_merged(),
+        &mut done,
+    );
+    let deleted_count = done.deleted.len();
 
-    for branch in &branches_to_delete {
-        match git::delete_branch(&repo, branch, cli.force) {
-            Ok(_) => {
-                println!("Deleted local branch '{}'", branch);
-                deleted_count += 1;
-            }
-            Err(e) => {
-                eprintln!("Skipped local branch '{}': {}", branch, e);
-                skipped_count += 1;
+    // Write the report even if deletion stopped part-way (e.g. a cancelled
+    // remote prompt), so branches that are already gone are still recorded
+    if let Some(path) = &cli.report {
+        let report = Report {
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            repo: repo_root.clone(),
+            base: base_branch.clone(),
+            merge_target: (merge_target != base_branch).then(|| merge_target.clone()),
+            deleted: done.deleted,
+        };
+        if let Err(e) = append_report(path, &report) {
+            if outcome.is_ok() {
+                return Err(e);
             }
+            eprintln!("Warning: {:#}", e);
         }
     }
+    outcome?;
 
-    if skipped_count > 0 {
+    if done.skipped > 0 {
         println!(
             "\nDeleted {} local branches ({} skipped)",
-            deleted_count, skipped_count
+            deleted_count, done.skipped
         );
     } else {
         println!("\nDeleted {} local branches", deleted_count);
     }
 
+    if cli.remote {
+        println!("Deleted {} remote branches", done.remote_deleted);
+    }
+
     Ok(())
 }
+
+/// What a deletion run got done, kept up to date as it goes
+#[derive(Default)]
+struct Deletion {
+    deleted: Vec<DeletedBranch>,
+    skipped: usize,
+    remote_deleted: usize,
+}
+
+/// Delete each branch, and with --remote its remote branch, recording progress in `done`
+///
+/// `force` deletes unmerged branches too; merged ones (per `is_merged`) are
+/// always force-deleted, since their merge status was already checked. Each
+/// deletion is logged under `log_as` (the repo root) unless that is `None`. Stops
+/// at the first error (e.g. a cancelled remote prompt), leaving `done`
+/// describing everything deleted up to that point.
+fn delete_branches(
+    cli: &Cli,
+    repo: &git2::Repository,
+    branches: &[String],
+    log_as: Option<&str>,
+    force: bool,
+    is_merged: impl Fn(&str) -> bool,
+    done: &mut Deletion,
+) -> Result<()> {
+    for branch in branches {
+        // Record the tip and merge status before the branch is gone
+        let tip = repo
+            .find_branch(branch, git2::BranchType::Local)
+            .ok()
+            .and_then(|b| b.get().target())
+            .map(|oid| oid.to_string())
+            .unwrap_or_default();
+        let merged = is_merged(branch);
+
+        // Look up the remote branch before the local branch (and its upstream config) is gone
+        let remote_branch = if cli.remote {
+            git::find_remote_branch(repo, branch).unwrap_or_default()
+        } else {
+            None
+        };
+
+        let force = force || merged;
+        match git::delete_branch(repo, branch, force) {
+            Ok(_) => {
+                // The tip is all that's needed to recover: git branch <name> <sha>
+                println!(
+                    "Deleted local branch '{}' (was {})",
+                    branch,
+                    &tip[..tip.len().min(7)]
+                );
+                if let Some(repo_root) = log_as {
+                    if let Err(e) = log_deleted_branch(repo_root, branch, &tip) {
+                        eprintln!("Warning: could not write {}: {}", DELETED_LOG, e);
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Skipped local branch '{}': {}", branch, e);
+                done.skipped += 1;
+                continue;
+            }
+        }
+
+        done.deleted.push(DeletedBranch {
+            name: branch.clone(),
+            tip,
+            merged,
+            remote_deleted: false,
+        });
+
+        if let Some((remote, remote_name)) = remote_branch {
+            let confirmed = cli.no_remote_prompt
+                || Confirm::new(&format!(
+                    "Delete remote branch '{}/{}'?",
+                    remote, remote_name
+                ))
+                .with_default(false)
+                .prompt()?;
+
+            if confirmed {
+                match git::delete_remote_branch(repo, &remote, &remote_name) {
+                    Ok(_) => {
+                        println!("Deleted remote branch '{}/{}'", remote, remote_name);
+                        done.remote_deleted += 1;
+                        if let Some(record) = done.deleted.last_mut() {
+                            record.remote_deleted = true;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "Failed to delete remote branch '{}/{}': {}",
+                            remote, remote_name, e
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Append one JSON line to the audit report, creating the file if needed
+fn append_report(path: &Path, report: &Report) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow!("Failed to open report {}: {}", path.display(), e))?;
+    writeln!(file, "{}", serde_json::to_string(report)?)?;
+    Ok(())
+}
+
+/// Undo trail of every deleted branch, under `~/.git-utils`
+const DELETED_LOG: &str = "deleted-branches.log";
+
+/// Append `<timestamp>\t<repo>\t<branch>\t<tip>` to the deleted-branches log
+fn log_deleted_branch(repo: &str, branch: &str, tip: &str) -> std::io::Result<()> {
+    let Some(dir) = config::git_utils_dir() else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(&dir)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(DELETED_LOG))?;
+    writeln!(
+        file,
+        "{}\t{}\t{}\t{}",
+        humantime::format_rfc3339_seconds(SystemTime::now()),
+        repo,
+        branch,
+        tip
+    )
+}
+
+/// Suffix shown after a branch name, e.g. " [merged] (3 weeks ago)"
+fn branch_annotations(status: MergeStatus, age: Option<i64>) -> String {
+    let mut annotations = String::new();
+    match status {
+        MergeStatus::Merged => annotations.push_str(" [merged]"),
+        MergeStatus::Squashed => annotations.push_str(" [squashed]"),
+        MergeStatus::Unmerged => {}
+    }
+    if let Some(age) = age {
+        annotations.push_str(&format!(" ({})", term::format_age(age)));
+    }
+    annotations
+}
+
+/// Print the local and remote deletions that would be performed
+///
+/// `annotate` returns the suffix shown after each branch name.
+fn print_dry_run(
+    repo: &git2::Repository,
+    branches: &[String],
+    remote: bool,
+    annotate: impl Fn(&str) -> String,
+) {
+    println!("\nDry run, nothing will be deleted:");
+    let mut remote_count = 0;
+    let mut remotes = BTreeSet::new();
+    for branch in branches {
+        println!(
+            "  Would delete local branch '{}'{}",
+            branch,
+            annotate(branch)
+        );
+        if !remote {
+            continue;
+        }
+        if let Ok(Some((remote, remote_name))) = git::find_remote_branch(repo, branch) {
+            println!(
+                "  Would delete remote branch '{}/{}' (git {})",
+                remote,
+                remote_name,
+                git::plan_delete_remote_branch(&remote, &remote_name).join(" ")
+            );
+            remote_count += 1;
+            remotes.insert(remote);
+        }
+    }
+
+    println!("\nWould delete {} local branches", branches.len());
+    if remote {
+        println!("Would delete {} remote branches", remote_count);
+        if !remotes.is_empty() {
+            println!(
+                "Remotes touched: {}",
+                remotes.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_annotations() {
+        assert_eq!(branch_annotations(MergeStatus::Unmerged, None), "");
+        assert_eq!(
+            branch_annotations(MergeStatus::Merged, Some(3 * 7 * 24 * 3600)),
+            " [merged] (3 weeks ago)"
+        );
+        assert_eq!(
+            branch_annotations(MergeStatus::Squashed, None),
+            " [squashed]"
+        );
+    }
+}
@@ -1,98 +1,563 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{anyhow, Result};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
 use git_utils_core::git;
-use inquire::Select;
+use git_utils_core::term::{self, ColorChoice};
+use inquire::ui::RenderConfig;
+use inquire::validator::Validation;
+use inquire::{Confirm, Select, Text};
+use std::io::IsTerminal;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Parser)]
-#[command(name = "git-branch-switch")]
+#[command(name = "git-branch-switch", version)]
 #[command(about = "Interactive branch switcher", long_about = None)]
 struct Cli {
-    /// Branch name or pattern to filter
+    /// Fuzzy pattern to filter branches by; matches are ranked best first
+    /// (e.g. `frmn` matches `feature/remote-name`); `-` switches to the previous branch
     branch_pattern: Option<String>,
 
-    /// Show recently used branches
+    /// Show recently used branches (the last 20, most recent first)
     #[arg(short, long)]
     recent: bool,
 
-    /// Show only merged branches
-    #[arg(short, long)]
-    merged: bool,
+    /// Show only branches merged into COMMIT (default: the detected base branch);
+    /// COMMIT must be attached with `=`, as in --merged=origin/main
+    #[arg(
+        short,
+        long,
+        value_name = "COMMIT",
+        num_args = 0..=1,
+        require_equals = true,
+        conflicts_with = "no_merged"
+    )]
+    merged: Option<Option<String>>,
+
+    /// Show only branches not merged into COMMIT (default: the detected base branch);
+    /// COMMIT must be attached with `=`, as in --no-merged=origin/main
+    #[arg(long, value_name = "COMMIT", num_args = 0..=1, require_equals = true)]
+    no_merged: Option<Option<String>>,
+
+    /// Don't warn about unpushed commits on the branch being left
+    #[arg(long)]
+    no_warn: bool,
 
-    /// Show only unmerged branches
+    /// Print the directory to cd into (the branch's worktree, or the repository root)
     #[arg(long)]
-    no_merged: bool,
+    print_path: bool,
+
+    /// When to use colors: auto, always, never
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Also offer remote branches with no local counterpart (listed as
+    /// `origin/<name>`); selecting one creates a local branch tracking it
+    #[arg(short = 'a', long, visible_alias = "all")]
+    create_from_remote: bool,
+
+    /// Sort order for the branch list (default: committerdate)
+    #[arg(long, value_enum, conflicts_with = "recent")]
+    sort: Option<SortOrder>,
+
+    /// Switch straight to the N-th most recently used branch without a menu
+    /// (1 is the previous branch, like `git switch -`)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["branch_pattern", "recent", "merged", "no_merged", "create_from_remote", "sort", "create"]
+    )]
+    last: Option<u64>,
+
+    /// Create a branch named BRANCH_PATTERN and switch to it without a menu
+    #[arg(short, long, requires = "branch_pattern")]
+    create: bool,
+
+    /// Revision to start a newly created branch from (default: HEAD)
+    #[arg(long, value_name = "REV")]
+    base: Option<String>,
+
+    /// Discard uncommitted changes instead of refusing to switch
+    #[arg(short, long)]
+    force: bool,
+
+    /// Show each branch's last commit age and author in the list
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SortOrder {
+    /// Alphabetical order
+    Name,
+    /// Recently visited branches first (by reflog), then the rest alphabetically
+    Recent,
+    /// Newest tip commit first
+    Committerdate,
+}
+
+fn main() -> ExitCode {
+    term::exit_code(run())
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn run() -> Result<()> {
+    let mut cli = Cli::parse();
+
+    // `git branch-switch -` is shorthand for `--last 1`, like `git switch -`
+    if cli.branch_pattern.as_deref() == Some("-") {
+        // Same conflicts as --last, which clap can't check for a positional value
+        let conflicting = [
+            (cli.recent, "--recent"),
+            (cli.merged.is_some(), "--merged"),
+            (cli.no_merged.is_some(), "--no-merged"),
+            (cli.create, "--create"),
+            (cli.sort.is_some(), "--sort"),
+            (cli.create_from_remote, "--create-from-remote"),
+        ];
+        if let Some((_, flag)) = conflicting.iter().find(|(set, _)| *set) {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("'-' (the previous branch) cannot be used with '{}'", flag),
+                )
+                .exit();
+        }
+        cli.branch_pattern = None;
+        cli.last = Some(1);
+    }
+
+    if !term::use_color(cli.color) {
+        inquire::set_global_render_config(RenderConfig::empty());
+    }
 
     let repo = git::open_repo()?;
-    let current_branch = git::get_current_branch(&repo)?;
+    reject_spaced_merge_target(&cli, &repo);
+    // A detached HEAD has no current branch to leave out of the list
+    let current_branch = if repo.head_detached()? {
+        None
+    } else {
+        Some(git::get_current_branch(&repo)?)
+    };
+
+    if let Some(n) = cli.last {
+        // One extra in case the current branch is among them
+        let recent: Vec<String> = git::get_recent_branches(&repo, Some(n as usize + 1))?
+            .into_iter()
+            .filter(|b| Some(b) != current_branch.as_ref())
+            .collect();
+        let branch = recent
+            .get(n as usize - 1)
+            .ok_or_else(|| match recent.len() {
+                0 => anyhow!("No previous branch found in the reflog"),
+                len => anyhow!(
+                    "Only {} recently used branch{} in the reflog",
+                    len,
+                    if len == 1 { "" } else { "es" }
+                ),
+            })?;
+        return switch_to(&cli, &repo, current_branch.as_deref(), branch);
+    }
+
+    if cli.create {
+        let name = cli.branch_pattern.as_deref().unwrap_or_default();
+        return create_and_switch(&cli, &repo, current_branch.as_deref(), name);
+    }
 
     // Get branches
     let mut branches = if cli.recent {
-        git::get_recent_branches(&repo)?
+        git::get_recent_branches(&repo, Some(git::DEFAULT_RECENT_LIMIT))?
     } else {
         git::get_local_branches(&repo)?
     };
 
-    // Filter by pattern if provided
-    if let Some(pattern) = &cli.branch_pattern {
-        branches.retain(|b| b.contains(pattern));
+    // Revision to measure merge status against (explicit --merged/--no-merged value wins)
+    let explicit_target = cli.merged.clone().or(cli.no_merged.clone()).flatten();
+    let merge_target = match explicit_target {
+        Some(target) => Some(target),
+        None => git::detect_base_branch(&repo).ok(),
+    };
+    // A target that doesn't resolve is an error, not an empty list
+    if let Some(target) = &merge_target {
+        git::resolve_base_commit(&repo, target)?;
     }
 
     // Filter by merge status
-    if cli.merged || cli.no_merged {
-        let base_branch = git::detect_base_branch(&repo)?;
-        branches.retain(|b| {
-            if let Ok(is_merged) = git::is_branch_merged(&repo, b, &base_branch) {
-                if cli.merged {
-                    is_merged
-                } else {
-                    !is_merged
-                }
-            } else {
-                false
+    if cli.merged.is_some() || cli.no_merged.is_some() {
+        let target = merge_target
+            .as_deref()
+            .ok_or(git_utils_core::Error::BaseBranchNotFound)?;
+        let mut kept = Vec::new();
+        for branch in branches {
+            if git::is_branch_merged(&repo, &branch, target)? == cli.merged.is_some() {
+                kept.push(branch);
             }
-        });
+        }
+        branches = kept;
+    }
+
+    // Sort branches (--recent keeps the reflog order)
+    match cli.sort {
+        Some(SortOrder::Name) => branches.sort(),
+        Some(SortOrder::Recent) => git::sort_branches_by_recent(&repo, &mut branches)?,
+        Some(SortOrder::Committerdate) => git::sort_branches_by_commit_date(&repo, &mut branches),
+        None if cli.recent => {}
+        None => git::sort_branches_by_commit_date(&repo, &mut branches),
     }
 
     // Remove current branch from list
-    branches.retain(|b| b != &current_branch);
+    branches.retain(|b| Some(b) != current_branch.as_ref());
+
+    // Filter by pattern if provided, best matches first
+    let pattern = cli.branch_pattern.as_deref().unwrap_or_default();
+    term::rank_by_pattern(&mut branches, pattern, |b| b);
+
+    // Remote branches that could be checked out as new local branches
+    let mut remote_branches = if cli.create_from_remote {
+        git::get_remote_only_branches(&repo)?
+    } else {
+        Vec::new()
+    };
+    term::rank_by_pattern(&mut remote_branches, pattern, |(_, name)| name);
 
-    if branches.is_empty() {
-        println!("No branches found");
+    if branches.is_empty() && remote_branches.is_empty() {
+        // Offer to create a branch named after a pattern that matched nothing
+        if let Some(name) = cli.branch_pattern.as_deref() {
+            let exists = git::get_local_branches(&repo)?.iter().any(|b| b == name);
+            if !exists && git2::Branch::name_is_valid(name)? && std::io::stdin().is_terminal() {
+                let create = Confirm::new(&format!(
+                    "No branches found. Create branch '{}' from {}?",
+                    name,
+                    cli.base.as_deref().unwrap_or("HEAD")
+                ))
+                .with_default(false)
+                .prompt()?;
+                if create {
+                    return create_and_switch(&cli, &repo, current_branch.as_deref(), name);
+                }
+                return Ok(());
+            }
+        }
+        info(&cli, "No branches found");
         return Ok(());
     }
 
     // Add merge status annotations
-    let base_branch = git::detect_base_branch(&repo).ok();
-    let branch_labels: Vec<String> = branches
-        .iter()
-        .map(|b| {
-            if let Some(base) = &base_branch {
-                if let Ok(true) = git::is_branch_merged(&repo, b, base) {
-                    format!("{} [merged]", b)
-                } else {
-                    b.clone()
-                }
-            } else {
-                b.clone()
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let choices = build_choices(
+        &branches,
+        &remote_branches,
+        |b| {
+            merge_target
+                .as_ref()
+                .is_some_and(|base| git::is_branch_merged(&repo, b, base).unwrap_or(false))
+        },
+        |b| {
+            if !cli.verbose {
+                return None;
             }
-        })
-        .collect();
+            let tip = git::get_branch_tip_summary(&repo, b).ok()?;
+            Some(format!(
+                "{}, {}",
+                term::format_age((now - tip.time).max(0)),
+                tip.author
+            ))
+        },
+    );
+    let labels: Vec<String> = choices.iter().map(|(label, _)| label.clone()).collect();
 
     // Interactive selection
-    let selection = Select::new("Select a branch:", branch_labels)
+    let selection = Select::new("Select a branch:", labels)
         .with_help_message("Use arrow keys to navigate, type to filter")
-        .prompt()?;
+        .raw_prompt()?;
 
-    // Extract branch name (remove [merged] suffix if present)
-    let selected_branch = selection.split_whitespace().next().unwrap();
+    // Map the chosen label back to the branch it was built from
+    let selected_branch = match &choices[selection.index].1 {
+        Choice::Local(branch) => branch.clone(),
+        Choice::Remote { remote, name } => {
+            let local_name = choose_local_name(&git::get_local_branches(&repo)?, name)?;
+            git::create_tracking_branch(&repo, &local_name, remote, name)?;
+            info(
+                &cli,
+                &format!(
+                    "Created branch '{}' tracking '{}/{}'",
+                    local_name, remote, name
+                ),
+            );
+            local_name
+        }
+    };
+    switch_to(&cli, &repo, current_branch.as_deref(), &selected_branch)
+}
+
+/// Create `name` at `--base` (or HEAD) and switch to it
+fn create_and_switch(
+    cli: &Cli,
+    repo: &git2::Repository,
+    current_branch: Option<&str>,
+    name: &str,
+) -> Result<()> {
+    if git::get_local_branches(repo)?.iter().any(|b| b == name) {
+        return Err(anyhow!("Branch '{}' already exists", name));
+    }
+    git::create_branch(repo, name, cli.base.as_deref())?;
+    eprintln!(
+        "Created branch '{}' from {}",
+        name,
+        cli.base.as_deref().unwrap_or("HEAD")
+    );
+    switch_to(cli, repo, current_branch, name)
+}
+
+/// Switch to the chosen branch, or point at the worktree it's checked out in
+fn switch_to(
+    cli: &Cli,
+    repo: &git2::Repository,
+    current_branch: Option<&str>,
+    selected_branch: &str,
+) -> Result<()> {
+    // If the branch is checked out in another worktree, point there instead of switching
+    if let Some(path) = git::find_worktree_for_branch(repo, selected_branch)? {
+        if cli.print_path {
+            println!("{}", path.display());
+        } else {
+            println!(
+                "Branch '{}' is checked out in worktree: {}",
+                selected_branch,
+                path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    // Uncommitted changes could block the checkout or be carried over unnoticed
+    if !cli.force && git::has_uncommitted_changes(repo)? {
+        let stash = std::io::stdin().is_terminal()
+            && Confirm::new("You have uncommitted changes. Stash them before switching?")
+                .with_default(false)
+                .prompt()?;
+        if !stash {
+            return Err(anyhow!(
+                "You have uncommitted changes; commit or stash them first, or use --force to discard them"
+            ));
+        }
+        let message = format!("git-branch-switch: before switching to {}", selected_branch);
+        git::stash_changes(repo, &message)?;
+        eprintln!("Stashed uncommitted changes (restore them with `git stash pop`)");
+    }
+
+    // Warn about unpushed commits on the branch we're leaving
+    if let Some(current_branch) = current_branch.filter(|_| !cli.no_warn) {
+        if let Ok(Some((ahead, _))) = git::ahead_behind(repo, current_branch) {
+            if ahead > 0 {
+                eprintln!(
+                    "Warning: leaving branch '{}' with {} unpushed commit{}",
+                    current_branch,
+                    ahead,
+                    if ahead == 1 { "" } else { "s" }
+                );
+            }
+        }
+    }
+
+    // Leaving a detached HEAD: note where it was so the commit can be found again
+    if current_branch.is_none() {
+        if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+            eprintln!("Previous HEAD position was {}", &head.id().to_string()[..7]);
+        }
+    }
 
     // Switch branch
-    git::switch_branch(&repo, selected_branch)?;
-    println!("Switched to branch '{}'", selected_branch);
+    if cli.force {
+        git::force_switch_branch(repo, selected_branch)?;
+    } else {
+        git::switch_branch(repo, selected_branch)?;
+    }
+    info(cli, &format!("Switched to branch '{}'", selected_branch));
+    if cli.print_path {
+        println!("{}", git::get_repo_root(repo)?.display());
+    }
 
     Ok(())
 }
+
+/// Reject `--merged <rev>` written with a space
+///
+/// clap reads it as a bare `--merged` followed by the fuzzy pattern `<rev>`.
+/// When that pattern names a revision, the user almost certainly meant it
+/// as the merge target, so exit with a usage error pointing at `=`.
+fn reject_spaced_merge_target(cli: &Cli, repo: &git2::Repository) {
+    let Some(pattern) = &cli.branch_pattern else {
+        return;
+    };
+    let args: Vec<String> = std::env::args().collect();
+    let flag = args.windows(2).find_map(|pair| {
+        let flag = match pair[0].as_str() {
+            "-m" | "--merged" => "--merged",
+            "--no-merged" => "--no-merged",
+            _ => return None,
+        };
+        (pair[1] == *pattern).then_some(flag)
+    });
+    if let Some(flag) = flag {
+        if repo.revparse_single(pattern).is_ok() {
+            Cli::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!(
+                        "'{0} {1}' filters by the pattern '{1}'; use '{0}={1}' to compare against that revision, or put the pattern before {0}",
+                        flag, pattern
+                    ),
+                )
+                .exit();
+        }
+    }
+}
+
+/// Print an informational message, on stderr with --print-path so that
+/// stdout only ever carries the path
+fn info(cli: &Cli, message: &str) {
+    if cli.print_path {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// A branch offered in the selection list
+#[derive(Debug, PartialEq)]
+enum Choice {
+    /// An existing local branch
+    Local(String),
+    /// A remote-only branch to create a local tracking branch from
+    Remote { remote: String, name: String },
+}
+
+/// Build `(label, choice)` pairs for the selection list
+///
+/// Labels carry annotations such as `[merged]`; the paired choice keeps the
+/// exact branch name so the selection never has to be parsed back out of a label.
+/// `details` (last commit info with --verbose) is shown in parentheses after
+/// the name, with names padded so the details line up.
+fn build_choices(
+    branches: &[String],
+    remote_branches: &[(String, String)],
+    is_merged: impl Fn(&str) -> bool,
+    details: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, Choice)> {
+    let width = branches
+        .iter()
+        .map(|b| b.chars().count())
+        .max()
+        .unwrap_or(0);
+    let local = branches.iter().map(|b| {
+        let mut label = match details(b) {
+            Some(details) => format!("{:<width$}  ({})", b, details),
+            None => b.clone(),
+        };
+        if is_merged(b) {
+            label.push_str(" [merged]");
+        }
+        (label, Choice::Local(b.clone()))
+    });
+
+    let remote = remote_branches.iter().map(|(remote, name)| {
+        (
+            format!("{}/{} [remote]", remote, name),
+            Choice::Remote {
+                remote: remote.clone(),
+                name: name.clone(),
+            },
+        )
+    });
+
+    local.chain(remote).collect()
+}
+
+/// Pick the local name for a branch created from a remote, prompting on collisions
+fn choose_local_name(local_branches: &[String], name: &str) -> Result<String> {
+    if !local_branches.iter().any(|b| b == name) {
+        return Ok(name.to_string());
+    }
+
+    let existing = local_branches.to_vec();
+    let local_name = Text::new(&format!(
+        "Local branch '{}' already exists. Name for the new branch:",
+        name
+    ))
+    .with_validator(move |input: &str| {
+        if input.trim().is_empty() {
+            Ok(Validation::Invalid("Branch name cannot be empty".into()))
+        } else if existing.iter().any(|b| b == input.trim()) {
+            Ok(Validation::Invalid(
+                "A local branch with this name exists".into(),
+            ))
+        } else {
+            Ok(Validation::Valid)
+        }
+    })
+    .prompt()?;
+
+    Ok(local_name.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_choices_aligns_details() {
+        let branches = vec!["main".to_string(), "feature/foo".to_string()];
+
+        let choices = build_choices(
+            &branches,
+            &[],
+            |b| b == "feature/foo",
+            |b| {
+                Some(format!(
+                    "2 days ago, {}",
+                    if b == "main" { "bob" } else { "alice" }
+                ))
+            },
+        );
+
+        let labels: Vec<_> = choices.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "main         (2 days ago, bob)",
+                "feature/foo  (2 days ago, alice) [merged]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_choices_keeps_exact_branch_names() {
+        let branches = vec!["feature/x".to_string(), "fix/a-[merged]-b".to_string()];
+        let remote_branches = vec![("origin".to_string(), "feature/x y".to_string())];
+
+        let choices = build_choices(&branches, &remote_branches, |b| b == "feature/x", |_| None);
+
+        let labels: Vec<_> = choices.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "feature/x [merged]",
+                "fix/a-[merged]-b",
+                "origin/feature/x y [remote]"
+            ]
+        );
+        assert_eq!(choices[0].1, Choice::Local("feature/x".to_string()));
+        assert_eq!(choices[1].1, Choice::Local("fix/a-[merged]-b".to_string()));
+        assert_eq!(
+            choices[2].1,
+            Choice::Remote {
+                remote: "origin".to_string(),
+                name: "feature/x y".to_string()
+            }
+        );
+    }
+}
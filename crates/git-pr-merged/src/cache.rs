@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::PullRequest;
+
+/// On-disk cache of fetched PR details, one JSON file per PR
+///
+/// Files live under `<cache dir>/git-utils/pr-merged/<host>/<repo path>/<number>.json`.
+/// Merged PRs rarely change, so entries are reused until they are older
+/// than the TTL. Cache errors are never fatal; a broken entry is refetched.
+pub struct PrCache {
+    dir: PathBuf,
+    ttl: Duration,
+    /// Skip reading (but still write) entries, for --refresh
+    refresh: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Seconds since the Unix epoch
+    fetched_at: u64,
+    pull: PullRequest,
+}
+
+impl PrCache {
+    /// Cache for a repository, or `None` if there is no cache directory
+    pub fn new(host: &str, path: &str, ttl: Duration, refresh: bool) -> Option<Self> {
+        let dir = dirs::cache_dir()?
+            .join("git-utils")
+            .join("pr-merged")
+            .join(host)
+            .join(path);
+        Some(Self::with_dir(dir, ttl, refresh))
+    }
+
+    fn with_dir(dir: PathBuf, ttl: Duration, refresh: bool) -> Self {
+        Self { dir, ttl, refresh }
+    }
+
+    /// Cached PR, if present and not expired
+    pub fn get(&self, number: u32) -> Option<PullRequest> {
+        if self.refresh {
+            return None;
+        }
+        let content = fs::read_to_string(self.entry_path(number)).ok()?;
+        let entry: Entry = serde_json::from_str(&content).ok()?;
+        let age = now().saturating_sub(entry.fetched_at);
+        (age < self.ttl.as_secs()).then_some(entry.pull)
+    }
+
+    /// Store fetched PRs, overwriting older entries
+    pub fn put(&self, pulls: &[PullRequest]) {
+        if fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        for pull in pulls {
+            let entry = Entry {
+                fetched_at: now(),
+                pull: pull.clone(),
+            };
+            if let Ok(content) = serde_json::to_string(&entry) {
+                let _ = fs::write(self.entry_path(pull.number), content);
+            }
+        }
+    }
+
+    fn entry_path(&self, number: u32) -> PathBuf {
+        self.dir.join(format!("{}.json", number))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn pull(number: u32) -> PullRequest {
+        serde_json::from_value(serde_json::json!({
+            "number": number,
+            "title": "Add feature",
+            "url": "https://github.com/o/r/pull/1",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cache_roundtrip_and_expiry() {
+        let dir = TempDir::new().unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        let cache = PrCache::with_dir(dir.path().join("repo"), day, false);
+        assert!(cache.get(1).is_none());
+        cache.put(&[pull(1)]);
+        assert_eq!(cache.get(1).unwrap().title, "Add feature");
+
+        // --refresh ignores cached entries
+        let refresh = PrCache::with_dir(dir.path().join("repo"), day, true);
+        assert!(refresh.get(1).is_none());
+
+        // Expired entries are not returned
+        let expired = PrCache::with_dir(dir.path().join("repo"), Duration::ZERO, false);
+        assert!(expired.get(1).is_none());
+    }
+}
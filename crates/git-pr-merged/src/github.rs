@@ -0,0 +1,465 @@
+use anyhow::{anyhow, Context, Result};
+use git_utils_core::auth;
+use std::process::Command;
+
+use crate::{Issue, PullRequest};
+
+/// Maximum number of PRs requested per GraphQL query
+const GRAPHQL_CHUNK_SIZE: usize = 100;
+
+/// Maximum number of PRs returned by a merged-PR search
+const SEARCH_LIMIT: usize = 1000;
+
+/// How requests reach the GitHub API
+pub enum Api {
+    /// Direct HTTP requests with a token from `GH_TOKEN`/`GITHUB_TOKEN`
+    Http { endpoint: String, token: String },
+    /// Requests through the `gh` CLI
+    Gh { token: String },
+}
+
+impl Api {
+    /// Pick the API transport for a GitHub host
+    ///
+    /// A token in the environment talks to the API directly, so `gh` doesn't
+    /// need to be installed (e.g. in CI). Otherwise the token comes from
+    /// `gh auth token` and requests go through `gh`.
+    pub fn resolve(host: &str) -> Option<Api> {
+        if let Some(token) = auth::env_token() {
+            return Some(Api::Http {
+                endpoint: graphql_endpoint(host),
+                token,
+            });
+        }
+        gh_auth_token().map(|token| Api::Gh { token })
+    }
+}
+
+/// GraphQL endpoint for github.com or a GitHub Enterprise Server host
+fn graphql_endpoint(host: &str) -> String {
+    if host == "github.com" {
+        "https://api.github.com/graphql".to_string()
+    } else {
+        format!("https://{}/api/graphql", host)
+    }
+}
+
+/// Token of the user logged in with `gh`
+fn gh_auth_token() -> Option<String> {
+    let output = Command::new("gh").args(["auth", "token"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let token = String::from_utf8(output.stdout).ok()?;
+    let token = token.trim();
+    if token.is_empty() {
+        None
+    } else {
+        Some(token.to_string())
+    }
+}
+
+/// Fetch PR details with a single GraphQL query
+///
+/// Each PR is requested through an aliased `pullRequest(number:)` field so
+/// that all of them come back in one round trip. PRs that don't exist are
+/// returned as `null` nodes and are simply skipped.
+pub fn fetch_pr_details_graphql(
+    api: &Api,
+    repo_info: &str,
+    pr_numbers: &[u32],
+) -> Result<Vec<PullRequest>> {
+    let mut pulls = Vec::new();
+    for chunk in pr_numbers.chunks(GRAPHQL_CHUNK_SIZE) {
+        let query = build_graphql_query(chunk);
+        let response = run_graphql(api, repo_info, &query, &[])?;
+        pulls.extend(parse_graphql_response(&response, chunk)?);
+    }
+
+    Ok(pulls)
+}
+
+/// Fetch issues referenced by number, skipping numbers that turn out to be PRs
+///
+/// A `#123` reference can point at either an issue or a pull request, so each
+/// number is resolved with `issueOrPullRequest` and only `Issue` nodes are kept.
+pub fn fetch_issues(api: &Api, repo_info: &str, numbers: &[u32]) -> Result<Vec<Issue>> {
+    let mut issues = Vec::new();
+    for chunk in numbers.chunks(GRAPHQL_CHUNK_SIZE) {
+        let fields = chunk
+            .iter()
+            .map(|n| {
+                format!(
+                    "n{}: issueOrPullRequest(number: {}) {{ __typename ... on Issue {{ number title url state }} }}",
+                    n, n
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let query = wrap_repository_query(&fields);
+        let response = run_graphql(api, repo_info, &query, &[])?;
+        issues.extend(parse_issues_response(&response, chunk)?);
+    }
+
+    Ok(issues)
+}
+
+/// Search merged PRs with a GitHub search query (e.g. `merged:A..B`)
+///
+/// Results are paged through until [`SEARCH_LIMIT`] PRs have been collected.
+pub fn search_merged_prs(api: &Api, repo_info: &str, query: &str) -> Result<Vec<PullRequest>> {
+    let search = format!("repo:{} is:pr is:merged {}", repo_info, query);
+    let graphql = format!(
+        "query($search: String!, $cursor: String) {{ search(query: $search, type: ISSUE, first: {}, after: $cursor) {{ pageInfo {{ hasNextPage endCursor }} nodes {{ ... on PullRequest {{ {} }} }} }} }}",
+        GRAPHQL_CHUNK_SIZE, PR_FIELDS
+    );
+
+    let mut pulls = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut variables = vec![("search", search.clone())];
+        if let Some(cursor) = &cursor {
+            variables.push(("cursor", cursor.clone()));
+        }
+        let response = run_query(api, &graphql, &variables)?;
+        let result = &response["data"]["search"];
+        if result.is_null() {
+            return Err(anyhow!(
+                "GraphQL search failed: {}",
+                error_message(&response)
+            ));
+        }
+
+        if let Some(nodes) = result["nodes"].as_array() {
+            pulls.extend(nodes.iter().filter_map(parse_pull_request));
+        }
+
+        cursor = result["pageInfo"]["endCursor"]
+            .as_str()
+            .map(|s| s.to_string());
+        if pulls.len() >= SEARCH_LIMIT
+            || !result["pageInfo"]["hasNextPage"].as_bool().unwrap_or(false)
+            || cursor.is_none()
+        {
+            break;
+        }
+    }
+
+    pulls.truncate(SEARCH_LIMIT);
+    Ok(pulls)
+}
+
+/// Run a GraphQL query against the repository
+fn run_graphql(
+    api: &Api,
+    repo_info: &str,
+    query: &str,
+    variables: &[(&str, String)],
+) -> Result<serde_json::Value> {
+    let (owner, name) = repo_info
+        .split_once('/')
+        .ok_or_else(|| anyhow!("Invalid repository: {}", repo_info))?;
+
+    let mut variables = variables.to_vec();
+    variables.push(("owner", owner.to_string()));
+    variables.push(("name", name.to_string()));
+    run_query(api, query, &variables)
+}
+
+/// Send a GraphQL query with string variables over the configured transport
+fn run_query(api: &Api, query: &str, variables: &[(&str, String)]) -> Result<serde_json::Value> {
+    match api {
+        Api::Http { endpoint, token } => run_query_http(endpoint, token, query, variables),
+        Api::Gh { token } => run_query_gh(token, query, variables),
+    }
+}
+
+fn run_query_http(
+    endpoint: &str,
+    token: &str,
+    query: &str,
+    variables: &[(&str, String)],
+) -> Result<serde_json::Value> {
+    let variables: serde_json::Map<String, serde_json::Value> = variables
+        .iter()
+        .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.clone())))
+        .collect();
+    let body = serde_json::json!({ "query": query, "variables": variables });
+
+    let response = ureq::post(endpoint)
+        .set("Authorization", &format!("bearer {}", token))
+        .set(
+            "User-Agent",
+            concat!("git-pr-merged/", env!("CARGO_PKG_VERSION")),
+        )
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string());
+
+    match response {
+        Ok(response) => {
+            let text = response
+                .into_string()
+                .context("Failed to read GitHub API response")?;
+            serde_json::from_str(&text).context("Failed to parse GitHub API response")
+        }
+        Err(ureq::Error::Status(code, response)) => {
+            let text = response.into_string().unwrap_or_default();
+            Err(anyhow!(http_error_message(code, &text)))
+        }
+        Err(e) => Err(anyhow!("GitHub API request failed: {}", e)),
+    }
+}
+
+fn run_query_gh(
+    token: &str,
+    query: &str,
+    variables: &[(&str, String)],
+) -> Result<serde_json::Value> {
+    let mut command = Command::new("gh");
+    command
+        .args(["api", "graphql"])
+        .arg("-f")
+        .arg(format!("query={}", query));
+    for (key, value) in variables {
+        command.arg("-f").arg(format!("{}={}", key, value));
+    }
+
+    let output = command
+        .env("GH_TOKEN", token)
+        .output()
+        .context("Failed to run gh api graphql")?;
+
+    // gh exits non-zero when the response contains errors (e.g. a PR
+    // number that doesn't exist), but still prints the partial data.
+    serde_json::from_slice(&output.stdout).map_err(|_| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow!("GraphQL request failed: {}", stderr.trim())
+    })
+}
+
+/// Turn an HTTP error from the GitHub API into an actionable message
+fn http_error_message(code: u16, body: &str) -> String {
+    let detail = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| body.trim().to_string());
+
+    match code {
+        401 => format!(
+            "GitHub rejected the token from GH_TOKEN/GITHUB_TOKEN (401: {}). Check that it is valid and not expired.",
+            detail
+        ),
+        403 => format!(
+            "GitHub denied access (403: {}). The token may lack access to this repository (classic tokens need the `repo` scope for private repositories), or the rate limit was exceeded.",
+            detail
+        ),
+        _ => format!("GitHub API request failed ({}): {}", code, detail),
+    }
+}
+
+fn error_message(response: &serde_json::Value) -> &str {
+    response["errors"][0]["message"]
+        .as_str()
+        .unwrap_or("no data in response")
+}
+
+fn wrap_repository_query(fields: &str) -> String {
+    format!(
+        "query($owner: String!, $name: String!) {{ repository(owner: $owner, name: $name) {{ {} }} }}",
+        fields
+    )
+}
+
+fn repository_data(response: &serde_json::Value) -> Result<&serde_json::Value> {
+    let repository = &response["data"]["repository"];
+    if repository.is_null() {
+        return Err(anyhow!(
+            "GraphQL request failed: {}",
+            error_message(response)
+        ));
+    }
+    Ok(repository)
+}
+
+/// Pull request fields requested by every PR query
+const PR_FIELDS: &str = "number title url body state mergedAt author { login } baseRefName isDraft mergedBy { login } mergeCommit { oid parents { totalCount } } labels(first: 20) { nodes { name } }";
+
+fn build_graphql_query(pr_numbers: &[u32]) -> String {
+    let fields = pr_numbers
+        .iter()
+        .map(|n| format!("pr{}: pullRequest(number: {}) {{ {} }}", n, n, PR_FIELDS))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    wrap_repository_query(&fields)
+}
+
+fn parse_graphql_response(
+    response: &serde_json::Value,
+    pr_numbers: &[u32],
+) -> Result<Vec<PullRequest>> {
+    let repository = repository_data(response)?;
+
+    Ok(pr_numbers
+        .iter()
+        .filter_map(|n| parse_pull_request(&repository[format!("pr{}", n)]))
+        .collect())
+}
+
+/// Parse a merged pull request node; `None` for missing or unmerged PRs
+fn parse_pull_request(pr: &serde_json::Value) -> Option<PullRequest> {
+    if pr.is_null() || pr["state"].as_str() != Some("MERGED") {
+        return None;
+    }
+    let number = pr["number"].as_u64()?;
+    Some(PullRequest {
+        number: number as u32,
+        title: pr["title"].as_str().unwrap_or("").to_string(),
+        url: pr["url"].as_str().unwrap_or("").to_string(),
+        merged_at: pr["mergedAt"].as_str().map(|s| s.to_string()),
+        author: pr["author"]["login"].as_str().map(|s| s.to_string()),
+        base_ref: pr["baseRefName"].as_str().map(|s| s.to_string()),
+        merge_commit: pr["mergeCommit"]["oid"].as_str().map(|s| s.to_string()),
+        merged_by: pr["mergedBy"]["login"].as_str().map(|s| s.to_string()),
+        merge_method: merge_method(&pr["mergeCommit"]),
+        is_draft: pr["isDraft"].as_bool().unwrap_or(false),
+        labels: pr["labels"]["nodes"]
+            .as_array()
+            .map(|nodes| {
+                nodes
+                    .iter()
+                    .filter_map(|l| l["name"].as_str())
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        body: pr["body"].as_str().map(|s| s.to_string()),
+        section: None,
+    })
+}
+
+/// Infer how a PR was merged from its merge commit's parent count
+///
+/// GitHub doesn't report the merge method, and squash and rebase merges both
+/// produce single-parent commits, so those two can't be told apart.
+fn merge_method(merge_commit: &serde_json::Value) -> Option<String> {
+    match merge_commit["parents"]["totalCount"].as_u64()? {
+        1 => Some("squash_or_rebase".to_string()),
+        2 => Some("merge".to_string()),
+        _ => None,
+    }
+}
+
+fn parse_issues_response(response: &serde_json::Value, numbers: &[u32]) -> Result<Vec<Issue>> {
+    let repository = repository_data(response)?;
+
+    let mut issues = Vec::new();
+    for n in numbers {
+        let node = &repository[format!("n{}", n)];
+        if node["__typename"].as_str() != Some("Issue") {
+            continue;
+        }
+        if let Some(number) = node["number"].as_u64() {
+            issues.push(Issue {
+                number: number as u32,
+                title: node["title"].as_str().unwrap_or("").to_string(),
+                url: node["url"].as_str().unwrap_or("").to_string(),
+                state: node["state"].as_str().unwrap_or("").to_lowercase(),
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphql_endpoint() {
+        assert_eq!(
+            graphql_endpoint("github.com"),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            graphql_endpoint("github.example.com"),
+            "https://github.example.com/api/graphql"
+        );
+    }
+
+    #[test]
+    fn test_http_error_message_is_actionable() {
+        let message = http_error_message(401, r#"{"message": "Bad credentials"}"#);
+        assert!(message.contains("Bad credentials"));
+        assert!(message.contains("GH_TOKEN/GITHUB_TOKEN"));
+    }
+
+    #[test]
+    fn test_build_graphql_query() {
+        let query = build_graphql_query(&[1, 23]);
+        assert!(query.contains("pr1: pullRequest(number: 1)"));
+        assert!(query.contains("pr23: pullRequest(number: 23)"));
+    }
+
+    #[test]
+    fn test_parse_graphql_response_skips_null_and_unmerged() {
+        let response = serde_json::json!({
+            "data": {
+                "repository": {
+                    "pr1": {
+                        "number": 1,
+                        "title": "Add feature",
+                        "url": "https://github.com/o/r/pull/1",
+                        "state": "MERGED",
+                        "mergedAt": "2024-01-01T00:00:00Z",
+                        "author": { "login": "poi2" },
+                        "mergeCommit": { "oid": "abc123", "parents": { "totalCount": 2 } }
+                    },
+                    "pr2": null,
+                    "pr3": {
+                        "number": 3,
+                        "title": "Open PR",
+                        "url": "https://github.com/o/r/pull/3",
+                        "state": "OPEN",
+                        "mergedAt": null,
+                        "author": { "login": "poi2" }
+                    }
+                }
+            },
+            "errors": [{ "message": "Could not resolve to a PullRequest with the number of 2." }]
+        });
+
+        let pulls = parse_graphql_response(&response, &[1, 2, 3]).unwrap();
+        assert_eq!(pulls.len(), 1);
+        assert_eq!(pulls[0].number, 1);
+        assert_eq!(pulls[0].author.as_deref(), Some("poi2"));
+        assert_eq!(pulls[0].merge_commit.as_deref(), Some("abc123"));
+        assert_eq!(pulls[0].merge_method.as_deref(), Some("merge"));
+    }
+
+    #[test]
+    fn test_parse_issues_response_skips_pull_requests() {
+        let response = serde_json::json!({
+            "data": {
+                "repository": {
+                    "n4": { "__typename": "PullRequest" },
+                    "n5": {
+                        "__typename": "Issue",
+                        "number": 5,
+                        "title": "Crash on startup",
+                        "url": "https://github.com/o/r/issues/5",
+                        "state": "CLOSED"
+                    },
+                    "n6": null
+                }
+            }
+        });
+
+        let issues = parse_issues_response(&response, &[4, 5, 6]).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].number, 5);
+        assert_eq!(issues[0].state, "closed");
+    }
+}
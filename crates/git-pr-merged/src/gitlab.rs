@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use std::process::Command;
+
+use crate::PullRequest;
+
+/// Fetch merge request details with `glab mr view`, one request per MR
+///
+/// MRs that don't exist or aren't merged are skipped.
+pub fn fetch_mr_details(host: &str, project: &str, mr_numbers: &[u32]) -> Result<Vec<PullRequest>> {
+    let repo = format!("https://{}/{}", host, project);
+
+    let mut pulls = Vec::new();
+    for n in mr_numbers {
+        let output = Command::new("glab")
+            .args([
+                "mr",
+                "view",
+                &n.to_string(),
+                "--repo",
+                &repo,
+                "--output",
+                "json",
+            ])
+            .output()
+            .context("Failed to run glab command")?;
+
+        if !output.status.success() {
+            continue;
+        }
+
+        let mr: serde_json::Value =
+            serde_json::from_slice(&output.stdout).context("Failed to parse glab output")?;
+        if let Some(pr) = parse_mr(&mr) {
+            pulls.push(pr);
+        }
+    }
+
+    Ok(pulls)
+}
+
+/// Convert a GitLab merge request (API JSON) into a `PullRequest`, if merged
+fn parse_mr(mr: &serde_json::Value) -> Option<PullRequest> {
+    if mr["state"].as_str() != Some("merged") {
+        return None;
+    }
+
+    let merge_method = if mr["squash_commit_sha"].is_string() {
+        "squash"
+    } else if mr["merge_commit_sha"].is_string() {
+        "merge"
+    } else {
+        "fast_forward"
+    };
+
+    Some(PullRequest {
+        number: mr["iid"].as_u64()? as u32,
+        title: mr["title"].as_str().unwrap_or("").to_string(),
+        url: mr["web_url"].as_str().unwrap_or("").to_string(),
+        merged_at: mr["merged_at"].as_str().map(|s| s.to_string()),
+        author: mr["author"]["username"].as_str().map(|s| s.to_string()),
+        base_ref: mr["target_branch"].as_str().map(|s| s.to_string()),
+        merge_commit: mr["merge_commit_sha"]
+            .as_str()
+            .or(mr["squash_commit_sha"].as_str())
+            .map(|s| s.to_string()),
+        merged_by: mr["merged_by"]["username"]
+            .as_str()
+            .or(mr["merge_user"]["username"].as_str())
+            .map(|s| s.to_string()),
+        merge_method: Some(merge_method.to_string()),
+        is_draft: mr["draft"].as_bool().unwrap_or(false),
+        labels: mr["labels"]
+            .as_array()
+            .map(|labels| {
+                labels
+                    .iter()
+                    .filter_map(|l| l.as_str())
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        body: mr["description"].as_str().map(|s| s.to_string()),
+        section: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mr() {
+        let mr = serde_json::json!({
+            "iid": 7,
+            "title": "Add feature",
+            "web_url": "https://gitlab.com/g/p/-/merge_requests/7",
+            "state": "merged",
+            "merged_at": "2024-01-01T00:00:00Z",
+            "author": { "username": "poi2" },
+            "target_branch": "main",
+            "labels": ["feature"],
+            "merge_commit_sha": null,
+            "squash_commit_sha": "abc123",
+            "draft": false
+        });
+
+        let pr = parse_mr(&mr).unwrap();
+        assert_eq!(pr.number, 7);
+        assert_eq!(pr.base_ref.as_deref(), Some("main"));
+        assert_eq!(pr.merge_method.as_deref(), Some("squash"));
+        assert_eq!(pr.labels, vec!["feature"]);
+
+        let open = serde_json::json!({ "iid": 8, "state": "opened" });
+        assert!(parse_mr(&open).is_none());
+    }
+}
@@ -1,33 +1,165 @@
 use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
 use clap::Parser;
 use git2::Repository;
+use git_utils_core::auth;
+use git_utils_core::config::Config;
+use git_utils_core::git;
+use git_utils_core::term::{self, ColorChoice};
+use inquire::ui::RenderConfig;
+use inquire::Confirm;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+mod cache;
+mod github;
+mod gitlab;
 
 #[derive(Parser)]
-#[command(name = "git-pr-merged")]
+#[command(name = "git-pr-merged", version)]
 #[command(about = "List merged pull requests in a revision range", long_about = None)]
 struct Cli {
     /// Revision range (e.g., v1.0.0..v1.1.0, HEAD~10..HEAD)
-    /// If not specified, uses latest tag..HEAD
+    /// If not specified, uses latest tag..HEAD, or the default branch..HEAD when
+    /// there are no tags (or history since --since)
     revision_range: Option<String>,
 
+    /// Remote whose repository is queried for PRs (default: origin, or the first
+    /// GitHub/GitLab remote); PRs missing there are looked up in the other remotes
+    #[arg(long, value_name = "NAME")]
+    remote: Option<String>,
+
+    /// Report which remote each PR was resolved against (on stderr)
+    #[arg(short, long)]
+    verbose: bool,
+
     /// Number of commits to check (alternative to revision range)
     #[arg(short = 'n', long, conflicts_with = "revision_range")]
     count: Option<usize>,
 
+    /// One section per release for the last N version tags, given as --all-tags=N
+    /// (all if N is omitted), using the ranges between consecutive tags
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        conflicts_with_all = ["revision_range", "count", "web", "open_all"]
+    )]
+    all_tags: Option<Option<usize>>,
+
+    /// Only include PRs merged at or after this time (RFC 3339 or YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_since)]
+    since: Option<DateTime<FixedOffset>>,
+
+    /// Only include PRs merged at or before this time (RFC 3339 or YYYY-MM-DD, inclusive)
+    #[arg(long, value_name = "DATE", value_parser = parse_until)]
+    until: Option<DateTime<FixedOffset>>,
+
     /// Open PR list in web browser
     #[arg(short, long)]
     web: bool,
 
-    /// Output format: text (default), json, markdown, plain
+    /// Open every fetched PR in its own browser tab
+    #[arg(long, conflicts_with = "web")]
+    open_all: bool,
+
+    /// With --open-all, open at most this many PRs
+    #[arg(long, value_name = "N", requires = "open_all")]
+    max_open: Option<usize>,
+
+    /// Also list closed issues referenced by commit messages
+    #[arg(long)]
+    issues: bool,
+
+    /// Regex matching PR references in commit subjects; capture group 1 is the number
+    /// (default: git-pr-merged.pattern config, else `#(\d+)`)
+    #[arg(long, value_name = "REGEX")]
+    pattern: Option<String>,
+
+    /// Ignore this PR number (can be repeated)
+    #[arg(long, value_name = "NUMBER")]
+    exclude_pr: Vec<u32>,
+
+    /// Only include PRs merged into this base branch (e.g. main)
+    #[arg(long, value_name = "BRANCH")]
+    target: Option<String>,
+
+    /// Only include PRs carrying this label (can be repeated; any label matches)
+    #[arg(long, value_name = "NAME")]
+    label: Vec<String>,
+
+    /// Leave out PRs carrying this label (can be repeated)
+    #[arg(long, value_name = "NAME")]
+    exclude_label: Vec<String>,
+
+    /// Group PRs into sections (configured via git-pr-merged.section.<name> = label1,label2;
+    /// one section per label if none are configured)
+    #[arg(long, value_enum)]
+    group_by: Option<GroupBy>,
+
+    /// Sort PRs by this field (default: commit log order)
+    #[arg(long, value_enum)]
+    sort: Option<SortKey>,
+
+    /// Reverse the sort order (PRs without a merge time still come last)
+    #[arg(long, requires = "sort")]
+    reverse: bool,
+
+    /// Include PR descriptions in JSON and Markdown output, truncated to N characters
+    /// if given as --with-body=N
+    #[arg(long, value_name = "N", num_args = 0..=1, require_equals = true)]
+    with_body: Option<Option<usize>>,
+
+    /// How long cached PR details are reused (e.g. 24h, 7d)
+    #[arg(long, value_name = "DURATION", default_value = "24h", value_parser = humantime::parse_duration)]
+    cache_ttl: std::time::Duration,
+
+    /// Don't read or write the PR details cache
+    #[arg(long, conflicts_with = "refresh")]
+    no_cache: bool,
+
+    /// Refetch all PR details and overwrite the cache
+    #[arg(long)]
+    refresh: bool,
+
+    /// Print a summary of PR and author counts after text or Markdown output
+    #[arg(long)]
+    stats: bool,
+
+    /// Output format: text (default), json, markdown, plain, release-notes
     #[arg(long, default_value = "text")]
     format: OutputFormat,
+
+    /// Map a Conventional Commits type to a release-notes section, e.g. perf=Performance
+    /// (can be repeated)
+    #[arg(long, value_name = "TYPE=SECTION", value_parser = parse_section_mapping)]
+    section_map: Vec<(String, String)>,
+
+    /// When to use colors and OSC 8 links: auto, always, never
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Pretty-print JSON output (default when stdout is a terminal)
+    #[arg(long, conflicts_with = "compact")]
+    pretty: bool,
+
+    /// Compact JSON output (default when stdout is piped)
+    #[arg(long)]
+    compact: bool,
+
+    /// Write the output to FILE instead of stdout (text format is written
+    /// without OSC 8 links)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
 }
 
-#[derive(Clone, Debug, clap::ValueEnum)]
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
 enum OutputFormat {
-    /// OSC 8 terminal links (default)
+    /// OSC 8 terminal links (default; plain when --color disables styling)
     Text,
     /// JSON format
     Json,
@@ -35,9 +167,50 @@ enum OutputFormat {
     Markdown,
     /// Plain text without OSC 8
     Plain,
+    /// Markdown table with number, title, author and merge date columns
+    Table,
+    /// Markdown grouped by Conventional Commits type (feat:, fix:, ...); overrides --group-by
+    ReleaseNotes,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum SortKey {
+    /// PR number
+    Number,
+    /// Merge time
+    MergedAt,
+    /// PR title
+    Title,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, clap::ValueEnum)]
+enum GroupBy {
+    /// Place each PR under the first section whose labels it carries
+    Label,
+}
+
+/// Section name for PRs that match no configured section
+const UNCATEGORIZED_SECTION: &str = "Uncategorized";
+
+/// Section name for PRs without any labels
+const UNLABELED_SECTION: &str = "Unlabeled";
+
+/// Release-notes sections and the Conventional Commits types they collect
+const COMMIT_TYPE_SECTIONS: &[(&str, &[&str])] = &[
+    ("Features", &["feat"]),
+    ("Bug Fixes", &["fix"]),
+    (
+        "Maintenance",
+        &[
+            "chore", "refactor", "perf", "build", "ci", "docs", "style", "test", "revert",
+        ],
+    ),
+];
+
+/// Release-notes section for PRs without a recognized type
+const OTHER_SECTION: &str = "Other";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct PullRequest {
     number: u32,
     title: String,
@@ -46,6 +219,33 @@ struct PullRequest {
     merged_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merge_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merged_by: Option<String>,
+    /// GitHub: "merge" or "squash_or_rebase" (by merge commit parents);
+    /// GitLab: "merge", "squash" or "fast_forward"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merge_method: Option<String>,
+    #[serde(default)]
+    is_draft: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<String>,
+    /// PR description; only kept with --with-body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    section: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Issue {
+    number: u32,
+    title: String,
+    url: String,
+    state: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -53,121 +253,629 @@ struct Output {
     range: String,
     platform: String,
     pulls: Vec<PullRequest>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    issues: Vec<Issue>,
+}
+
+/// Ask for confirmation before --open-all opens more tabs than this
+const OPEN_ALL_CONFIRM_THRESHOLD: usize = 10;
+
+fn main() -> ExitCode {
+    term::exit_code(run())
 }
 
-fn main() -> Result<()> {
+fn run() -> Result<()> {
     let cli = Cli::parse();
 
+    if !term::use_color(cli.color) {
+        inquire::set_global_render_config(RenderConfig::empty());
+    }
+
     let repo = Repository::discover(".")
         .context("Not a git repository. Run this command from within a git repository.")?;
 
-    // Determine revision range
-    let revision_range = if let Some(range) = cli.revision_range {
-        range
+    // Determine revision ranges
+    let ranges = if let Some(limit) = cli.all_tags {
+        let ranges = tag_ranges(&git::get_version_tags(&repo)?, limit);
+        if ranges.is_empty() {
+            return Err(anyhow!("No version tags found (e.g. v1.2.3)"));
+        }
+        ranges
+    } else if let Some(range) = cli.revision_range.clone() {
+        vec![range]
     } else if let Some(count) = cli.count {
-        format!("HEAD~{}..HEAD", count)
+        vec![format!("HEAD~{}..HEAD", count)]
+    } else if cli.since.is_some() || cli.until.is_some() {
+        // Widen the default range to cover the requested window
+        vec![range_since(&repo, cli.since)?]
     } else {
-        // Use latest tag..HEAD
-        get_latest_tag(&repo)
-            .map(|tag| format!("{}..HEAD", tag))
-            .unwrap_or_else(|| "HEAD~10..HEAD".to_string())
+        vec![default_range(&repo)]
     };
 
-    // Check if gh command is available
-    if !is_gh_available() {
-        return Err(anyhow!(
-            "gh command not found. Please install GitHub CLI: https://cli.github.com/"
-        ));
+    // Get repository info (host and owner/repo) and pick the backend
+    let remote = resolve_remote(&repo, cli.remote.as_deref())?;
+
+    // Check if the platform's CLI is available (not needed with a token in
+    // the environment, which talks to the GitHub API directly)
+    match remote.platform {
+        Platform::GitHub if auth::env_token().is_none() && !is_command_available("gh") => {
+            return Err(anyhow!(
+                "gh command not found. Please install GitHub CLI (https://cli.github.com/) or set GH_TOKEN/GITHUB_TOKEN"
+            ));
+        }
+        Platform::GitLab if !is_command_available("glab") => {
+            return Err(anyhow!(
+                "glab command not found. Please install GitLab CLI: https://gitlab.com/gitlab-org/cli"
+            ));
+        }
+        _ => {}
+    }
+
+    let pattern = match cli.pattern.clone() {
+        Some(pattern) => Some(pattern),
+        None => Config::load_for_repo(&repo)?.get_string("git-pr-merged.pattern"),
+    };
+    let pattern = pattern.as_deref().map(compile_pr_pattern).transpose()?;
+
+    let mut outputs = Vec::new();
+    for range in ranges {
+        if let Some(output) = collect_range(&cli, &repo, &remote, pattern.as_ref(), range)? {
+            outputs.push(output);
+        }
+    }
+
+    let mut out: Box<dyn Write> = match &cli.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path).with_context(|| {
+            format!("Failed to create output file: {}", path.display())
+        })?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    // Terminal hyperlinks are meaningless in a file
+    let with_links = cli.output.is_none() && term::use_hyperlinks(cli.color);
+
+    match cli.format {
+        OutputFormat::Json => {
+            let pretty = term::use_pretty_json(cli.pretty, cli.compact);
+            if cli.all_tags.is_some() {
+                print_json(&mut out, &outputs, pretty)?;
+            } else if let Some(output) = outputs.first() {
+                print_json(&mut out, output, pretty)?;
+            }
+        }
+        format => {
+            for (i, output) in outputs.iter().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                match format {
+                    OutputFormat::Text => {
+                        print_text(&mut out, output, with_links, cli.all_tags.is_some())?
+                    }
+                    OutputFormat::Plain => {
+                        print_text(&mut out, output, false, cli.all_tags.is_some())?
+                    }
+                    OutputFormat::Table => print_table(&mut out, output)?,
+                    _ => print_markdown(&mut out, output)?,
+                }
+                if cli.stats {
+                    print_stats(&mut out, &output.pulls, format)?;
+                }
+            }
+        }
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Range used when none is given: latest tag..HEAD, else default branch..HEAD
+///
+/// The branch fallback is only used when HEAD has moved past the default
+/// branch; otherwise (e.g. on the default branch itself) the last ten commits
+/// are shown.
+fn default_range(repo: &Repository) -> String {
+    if let Some(tag) = git::get_latest_tag(repo) {
+        return format!("{}..HEAD", tag);
+    }
+
+    let head = repo.head().ok().and_then(|head| head.target());
+    if let Some(branch) = git::get_default_branch(repo) {
+        let base = repo
+            .revparse_single(&branch)
+            .ok()
+            .and_then(|object| object.peel_to_commit().ok())
+            .map(|commit| commit.id());
+        if base.is_some() && base != head {
+            return format!("{}..HEAD", branch);
+        }
     }
 
-    // Get repository info (owner/repo)
-    let repo_info = get_repo_info(&repo)?;
+    "HEAD~10..HEAD".to_string()
+}
 
+/// Collect merged PRs for one revision range
+///
+/// Returns `None` when there's nothing to print: no PRs in a single range,
+/// or the PRs were opened in the browser instead.
+fn collect_range(
+    cli: &Cli,
+    repo: &Repository,
+    remote: &RemoteRepo,
+    pattern: Option<&regex::Regex>,
+    revision_range: String,
+) -> Result<Option<Output>> {
     // Extract PR numbers from git log
-    let pr_numbers = extract_pr_numbers(&repo, &revision_range)?;
+    let mut pr_numbers = extract_pr_numbers(repo, &revision_range, remote.platform, pattern)?;
 
-    if pr_numbers.is_empty() {
-        println!("No merged pull requests found in range: {}", revision_range);
-        return Ok(());
+    // Heuristic fallback for repos whose commits don't reference PRs: search
+    // for PRs merged within the range's commit dates
+    let mut fallback_pulls = None;
+    if pr_numbers.is_empty() && remote.platform == Platform::GitHub {
+        if let (Some((since, until)), Some(api)) = (
+            commit_date_bounds(repo, &revision_range)?,
+            github::Api::resolve(&remote.host),
+        ) {
+            eprintln!(
+                "Note: no PR references found in {}; listing PRs merged between {} and {} instead (heuristic, by merge date)",
+                revision_range, since, until
+            );
+            let query = format!("merged:{}..{}", since, until);
+            let pulls = github::search_merged_prs(&api, &remote.path, &query)?;
+            pr_numbers = pulls.iter().map(|p| p.number).collect();
+            fallback_pulls = Some(pulls);
+        }
+    }
+
+    pr_numbers.retain(|n| !cli.exclude_pr.contains(n));
+
+    // With --all-tags, releases without PRs still get a (empty) section
+    if pr_numbers.is_empty() && cli.all_tags.is_none() {
+        // Issues closed in the range are still worth listing with --issues
+        if !cli.issues || cli.web {
+            println!("No merged pull requests found in range: {}", revision_range);
+            return Ok(None);
+        }
+        eprintln!("No merged pull requests found in range: {}", revision_range);
     }
 
     if cli.web {
-        open_in_browser(&repo_info, &pr_numbers)?;
-        return Ok(());
+        open_in_browser(&web_url(remote, &pr_numbers))?;
+        return Ok(None);
     }
 
-    // Fetch PR details using gh command
-    let pulls = fetch_pr_details(&repo_info, &pr_numbers)?;
+    // Resolve the GitHub API transport (env token or gh auth)
+    let api = match remote.platform {
+        Platform::GitHub => Some(github::Api::resolve(&remote.host).ok_or_else(|| {
+            anyhow!(
+                "GitHub token not found. Run `gh auth login` or set GH_TOKEN/GITHUB_TOKEN environment variable."
+            )
+        })?),
+        Platform::GitLab => None,
+    };
 
-    // Output results
-    let output = Output {
-        range: revision_range,
-        platform: "github".to_string(),
-        pulls,
+    let mut pulls = match fallback_pulls {
+        Some(pulls) => pulls
+            .into_iter()
+            .filter(|pr| pr_numbers.contains(&pr.number))
+            .collect(),
+        None => fetch_pulls_cached(cli, remote, api.as_ref(), &pr_numbers)?,
     };
+    report_resolved(cli, &pulls, remote);
 
-    match cli.format {
-        OutputFormat::Text => print_text(&output, true),
-        OutputFormat::Plain => print_text(&output, false),
-        OutputFormat::Json => print_json(&output)?,
-        OutputFormat::Markdown => print_markdown(&output),
+    // PRs not found on the chosen remote may belong to another one (e.g. a
+    // fork's upstream); keep commit log order when any turn up
+    let mut missing: Vec<u32> = pr_numbers
+        .iter()
+        .copied()
+        .filter(|n| !pulls.iter().any(|pr| pr.number == *n))
+        .collect();
+    if !missing.is_empty() {
+        for other in other_remotes(repo, remote)? {
+            let other_api = match other.platform {
+                Platform::GitHub => github::Api::resolve(&other.host),
+                Platform::GitLab => None,
+            };
+            let found = match fetch_pulls_cached(cli, &other, other_api.as_ref(), &missing) {
+                Ok(found) => found,
+                Err(e) => {
+                    if cli.verbose {
+                        eprintln!("Warning: lookup on remote '{}' failed: {}", other.name, e);
+                    }
+                    continue;
+                }
+            };
+            report_resolved(cli, &found, &other);
+            missing.retain(|n| !found.iter().any(|pr| pr.number == *n));
+            pulls.extend(found);
+            if missing.is_empty() {
+                break;
+            }
+        }
+        pulls.sort_by_key(|pr| pr_numbers.iter().position(|n| *n == pr.number));
     }
 
-    Ok(())
-}
+    // Sort before grouping; sections keep this order within each group
+    for pr in pulls.iter_mut() {
+        pr.body = match cli.with_body {
+            None => None,
+            Some(limit) => pr
+                .body
+                .take()
+                .map(|body| body.trim().to_string())
+                .filter(|body| !body.is_empty())
+                .map(|body| truncate_body(&body, limit)),
+        };
+    }
+    if let Some(key) = cli.sort {
+        sort_pulls(&mut pulls, key, cli.reverse);
+    }
 
-fn get_latest_tag(repo: &Repository) -> Option<String> {
-    let output = Command::new("git")
-        .args(["describe", "--tags", "--abbrev=0"])
-        .current_dir(repo.path().parent()?)
-        .output()
-        .ok()?;
+    // Assign PRs to Conventional Commits sections for release notes, else
+    // to label-based sections
+    if let OutputFormat::ReleaseNotes = cli.format {
+        let sections = commit_type_sections(&cli.section_map);
+        assign_commit_type_sections(&mut pulls, &sections, |pr| merge_commit_subject(repo, pr));
+    } else if let Some(GroupBy::Label) = cli.group_by {
+        let mut sections = Config::load_for_repo(repo)?.get_list_map("git-pr-merged.section");
+        if sections.is_empty() {
+            sections = label_sections(&pulls);
+        }
+        assign_sections(&mut pulls, &sections);
+    }
 
-    if output.status.success() {
-        String::from_utf8(output.stdout)
-            .ok()
-            .map(|s| s.trim().to_string())
-    } else {
-        None
+    // Warn about missing PRs
+    let fetched_numbers: std::collections::HashSet<u32> = pulls.iter().map(|p| p.number).collect();
+    for &num in &pr_numbers {
+        if !fetched_numbers.contains(&num) {
+            eprintln!(
+                "Warning: PR {}{} not found or not merged",
+                remote.platform.ref_prefix(),
+                num
+            );
+        }
+    }
+
+    // Keep only PRs merged into the requested base branch
+    if let Some(target) = &cli.target {
+        pulls.retain(|pr| pr.base_ref.as_deref() == Some(target.as_str()));
+    }
+
+    // Filter by merge time
+    if cli.since.is_some() || cli.until.is_some() {
+        pulls.retain(|pr| {
+            pr.merged_at
+                .as_deref()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| {
+                    cli.since.is_none_or(|since| t >= since)
+                        && cli.until.is_none_or(|until| t <= until)
+                })
+        });
+    }
+
+    // Filter by labels
+    if !cli.label.is_empty() {
+        pulls.retain(|pr| pr.labels.iter().any(|l| cli.label.contains(l)));
+    }
+    pulls.retain(|pr| !pr.labels.iter().any(|l| cli.exclude_label.contains(l)));
+
+    // Fetch closed issues referenced in commit messages
+    let issues = match (cli.issues, &api) {
+        (false, _) => Vec::new(),
+        (true, None) => {
+            eprintln!("Warning: --issues is only supported for GitHub repositories");
+            Vec::new()
+        }
+        (true, Some(api)) => {
+            let numbers: Vec<u32> = extract_issue_numbers(repo, &revision_range)?
+                .into_iter()
+                .filter(|n| !pr_numbers.contains(n))
+                .collect();
+            github::fetch_issues(api, &remote.path, &numbers)?
+                .into_iter()
+                .filter(|issue| issue.state == "closed")
+                .collect()
+        }
+    };
+
+    if cli.open_all {
+        open_all_in_browser(&pulls, cli.max_open)?;
+        return Ok(None);
+    }
+
+    Ok(Some(Output {
+        range: revision_range,
+        platform: remote.platform.as_str().to_string(),
+        pulls,
+        issues,
+    }))
+}
+
+/// Ranges between consecutive version tags, newest release first
+///
+/// `limit` keeps only the last N releases. The oldest tag's range covers all
+/// history up to it.
+fn tag_ranges(tags: &[String], limit: Option<usize>) -> Vec<String> {
+    let mut ranges: Vec<String> = tags
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, tag)| match i {
+            0 => tag.clone(),
+            _ => format!("{}..{}", tags[i - 1], tag),
+        })
+        .collect();
+    if let Some(limit) = limit {
+        ranges.truncate(limit);
     }
+    ranges
 }
 
-fn is_gh_available() -> bool {
-    Command::new("gh")
+fn is_command_available(program: &str) -> bool {
+    Command::new(program)
         .arg("--version")
         .output()
         .map(|output| output.status.success())
         .unwrap_or(false)
 }
 
-fn get_repo_info(repo: &Repository) -> Result<String> {
+/// Hosting platform of the `origin` remote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Platform {
+    GitHub,
+    GitLab,
+}
+
+impl Platform {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Platform::GitHub => "github",
+            Platform::GitLab => "gitlab",
+        }
+    }
+
+    /// Prefix used when referring to a PR/MR number (`#123` or `!123`)
+    fn ref_prefix(&self) -> &'static str {
+        match self {
+            Platform::GitHub => "#",
+            Platform::GitLab => "!",
+        }
+    }
+}
+
+/// A git remote: name, platform, host, and project path (e.g. owner/repo)
+struct RemoteRepo {
+    name: String,
+    platform: Platform,
+    host: String,
+    path: String,
+}
+
+/// Fetch PR details, reusing cached PRs unless --no-cache is given
+///
+/// PRs are returned in the order of `pr_numbers`.
+fn fetch_pulls_cached(
+    cli: &Cli,
+    remote: &RemoteRepo,
+    api: Option<&github::Api>,
+    pr_numbers: &[u32],
+) -> Result<Vec<PullRequest>> {
+    let cache = if cli.no_cache {
+        None
+    } else {
+        cache::PrCache::new(&remote.host, &remote.path, cli.cache_ttl, cli.refresh)
+    };
+    let Some(cache) = cache else {
+        return fetch_pulls(remote, api, pr_numbers);
+    };
+
+    let mut pulls = Vec::new();
+    let mut uncached = Vec::new();
+    for &number in pr_numbers {
+        match cache.get(number) {
+            Some(pr) => pulls.push(pr),
+            None => uncached.push(number),
+        }
+    }
+
+    if !uncached.is_empty() {
+        let fetched = fetch_pulls(remote, api, &uncached)?;
+        cache.put(&fetched);
+        pulls.extend(fetched);
+        pulls.sort_by_key(|pr| pr_numbers.iter().position(|n| *n == pr.number));
+    }
+    Ok(pulls)
+}
+
+/// Fetch PR details: batched GraphQL on GitHub (search as a fallback), glab on GitLab
+fn fetch_pulls(
+    remote: &RemoteRepo,
+    api: Option<&github::Api>,
+    pr_numbers: &[u32],
+) -> Result<Vec<PullRequest>> {
+    match (remote.platform, api) {
+        (Platform::GitHub, Some(api)) => {
+            github::fetch_pr_details_graphql(api, &remote.path, pr_numbers).or_else(|e| {
+                eprintln!(
+                    "Warning: GraphQL fetch failed ({}), falling back to search",
+                    e
+                );
+                fetch_pr_details(api, &remote.path, pr_numbers)
+            })
+        }
+        (Platform::GitHub, None) => Err(anyhow!(
+            "GitHub token not found for {}. Run `gh auth login` or set GH_TOKEN/GITHUB_TOKEN environment variable.",
+            remote.host
+        )),
+        (Platform::GitLab, _) => gitlab::fetch_mr_details(&remote.host, &remote.path, pr_numbers),
+    }
+}
+
+/// With --verbose, note which remote each PR was found on
+fn report_resolved(cli: &Cli, pulls: &[PullRequest], remote: &RemoteRepo) {
+    if !cli.verbose {
+        return;
+    }
+    for pr in pulls {
+        eprintln!(
+            "{}{} resolved against {} ({})",
+            remote.platform.ref_prefix(),
+            pr.number,
+            remote.name,
+            remote.path
+        );
+    }
+}
+
+/// Remotes other than the chosen one, assumed to be on the same platform,
+/// skipping ones that point at the same repository
+fn other_remotes(repo: &Repository, remote: &RemoteRepo) -> Result<Vec<RemoteRepo>> {
+    let mut others: Vec<RemoteRepo> = Vec::new();
+    for name in repo.remotes()?.iter().flatten() {
+        if name == remote.name {
+            continue;
+        }
+        let Some((host, path)) = repo
+            .find_remote(name)
+            .ok()
+            .and_then(|r| r.url().and_then(parse_remote_url))
+        else {
+            continue;
+        };
+        let same_repo = |r: &RemoteRepo| r.host == host && r.path == path;
+        if same_repo(remote) || others.iter().any(same_repo) {
+            continue;
+        }
+        others.push(RemoteRepo {
+            name: name.to_string(),
+            platform: remote.platform,
+            host,
+            path,
+        });
+    }
+    Ok(others)
+}
+
+/// Pick the remote to query: the one given with --remote, else `origin`,
+/// else the first remote on a recognized platform (with a note)
+fn resolve_remote(repo: &Repository, explicit: Option<&str>) -> Result<RemoteRepo> {
+    if let Some(name) = explicit {
+        return get_repo_info(repo, name);
+    }
+
+    let origin_error = match get_repo_info(repo, "origin") {
+        Ok(remote) => return Ok(remote),
+        Err(e) => e,
+    };
+    for name in repo.remotes()?.iter().flatten() {
+        if name == "origin" {
+            continue;
+        }
+        if let Ok(remote) = get_repo_info(repo, name) {
+            eprintln!(
+                "Note: {}; using remote '{}' ({}) instead",
+                origin_error, remote.name, remote.host
+            );
+            return Ok(remote);
+        }
+    }
+    Err(origin_error)
+}
+
+fn get_repo_info(repo: &Repository, remote_name: &str) -> Result<RemoteRepo> {
     let remote = repo
-        .find_remote("origin")
-        .context("No 'origin' remote found")?;
+        .find_remote(remote_name)
+        .with_context(|| format!("No '{}' remote found", remote_name))?;
 
     let url = remote.url().context("Invalid remote URL")?;
+    let (host, path) = parse_remote_url(url).context("Invalid remote URL")?;
+
+    // gitlab.com and self-hosted instances with "gitlab" in the host name are
+    // detected automatically; git-pr-merged.platform covers other hosts
+    let configured = Config::load_for_repo(repo)?.get_string("git-pr-merged.platform");
+    let platform = match configured.as_deref() {
+        Some("github") => Platform::GitHub,
+        Some("gitlab") => Platform::GitLab,
+        Some(other) => {
+            return Err(anyhow!(
+                "Invalid git-pr-merged.platform '{}' (expected github or gitlab)",
+                other
+            ))
+        }
+        None if host == "github.com" => Platform::GitHub,
+        None if host.contains("gitlab") => Platform::GitLab,
+        None => return Err(anyhow!("Not a GitHub or GitLab repository: {}", host)),
+    };
+
+    Ok(RemoteRepo {
+        name: remote_name.to_string(),
+        platform,
+        host,
+        path,
+    })
+}
 
-    // Parse GitHub URL (either SSH or HTTPS)
-    // SSH: git@github.com:owner/repo.git
-    // HTTPS: https://github.com/owner/repo.git
-    let repo_path = if url.starts_with("git@github.com:") {
-        url.trim_start_matches("git@github.com:")
-            .trim_end_matches(".git")
-    } else if url.contains("github.com/") {
-        url.split("github.com/")
-            .nth(1)
-            .context("Invalid GitHub URL")?
-            .trim_end_matches(".git")
+/// Split a remote URL into host and project path
+///
+/// Supports `git@host:path.git`, `ssh://git@host[:port]/path.git` and
+/// `https://host/path.git`.
+fn parse_remote_url(url: &str) -> Option<(String, String)> {
+    let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit('@').next()?;
+        (host.split(':').next()?, path)
     } else {
-        return Err(anyhow!("Not a GitHub repository"));
+        let (authority, path) = url.split_once(':')?;
+        (authority.rsplit('@').next()?, path)
     };
 
-    Ok(repo_path.to_string())
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((host.to_string(), path.to_string()))
+}
+
+/// Compile a user-supplied PR reference pattern
+///
+/// The PR number is read from the first capture group, so a pattern
+/// without one is rejected.
+fn compile_pr_pattern(pattern: &str) -> Result<regex::Regex> {
+    let regex = regex::Regex::new(pattern)
+        .map_err(|e| anyhow!("Invalid PR pattern '{}': {}", pattern, e))?;
+    if regex.captures_len() < 2 {
+        return Err(anyhow!(
+            "PR pattern '{}' has no capture group; wrap the number in parentheses, e.g. \\(#(\\d+)\\)$",
+            pattern
+        ));
+    }
+    Ok(regex)
 }
 
-fn extract_pr_numbers(repo: &Repository, range: &str) -> Result<Vec<u32>> {
+/// Extract PR numbers referenced by commits in the revision range
+///
+/// GitHub PRs are found as `#123` in commit subjects. For GitLab, full commit
+/// messages are scanned for `!123` (as in "See merge request group/project!123")
+/// as well as `#123`.
+///
+/// The range follows `git log` semantics, so for `A..B` the commit `A` itself
+/// (and its ancestors) are excluded: a PR referenced only by the lower-bound
+/// commit never shows up.
+///
+/// `pattern` overrides the platform's default reference pattern.
+fn extract_pr_numbers(
+    repo: &Repository,
+    range: &str,
+    platform: Platform,
+    pattern: Option<&regex::Regex>,
+) -> Result<Vec<u32>> {
+    let (format, default_pattern) = match platform {
+        Platform::GitHub => ("--format=%s", r"#(\d+)"),
+        Platform::GitLab => ("--format=%B", r"[#!](\d+)"),
+    };
+
     let output = Command::new("git")
-        .args(["log", "--format=%s", range])
+        .args(["log", format, range])
         .current_dir(repo.path().parent().context("Invalid repo path")?)
         .output()
         .context("Failed to run git log")?;
@@ -176,14 +884,19 @@ fn extract_pr_numbers(repo: &Repository, range: &str) -> Result<Vec<u32>> {
         return Err(anyhow!("Invalid revision range: {}", range));
     }
 
-    let log = String::from_utf8(output.stdout)?;
+    // Only scanned for references, so a commit message in another encoding
+    // shouldn't abort the whole run
+    let log = String::from_utf8_lossy(&output.stdout);
     let mut pr_numbers = Vec::new();
     let mut seen = std::collections::HashSet::new();
 
-    let pr_regex = regex::Regex::new(r"#(\d+)").unwrap();
+    let pr_regex = match pattern {
+        Some(pattern) => pattern.clone(),
+        None => regex::Regex::new(default_pattern).unwrap(),
+    };
 
     for line in log.lines() {
-        // Look for patterns like "#123" or "(#123)" in commit messages
+        // Look for patterns like "#123", "(#123)" or "!123" in commit messages
         for cap in pr_regex.captures_iter(line) {
             if let Some(num_str) = cap.get(1) {
                 if let Ok(num) = num_str.as_str().parse::<u32>() {
@@ -199,7 +912,113 @@ fn extract_pr_numbers(repo: &Repository, range: &str) -> Result<Vec<u32>> {
     Ok(pr_numbers)
 }
 
-fn fetch_pr_details(repo_info: &str, pr_numbers: &[u32]) -> Result<Vec<PullRequest>> {
+/// Extract `#N` references from full commit messages in the revision range
+///
+/// These may point at issues or PRs; the caller resolves which is which.
+fn extract_issue_numbers(repo: &Repository, range: &str) -> Result<Vec<u32>> {
+    let output = Command::new("git")
+        .args(["log", "--format=%B", range])
+        .current_dir(repo.path().parent().context("Invalid repo path")?)
+        .output()
+        .context("Failed to run git log")?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Invalid revision range: {}", range));
+    }
+
+    let log = String::from_utf8_lossy(&output.stdout);
+    let mut numbers = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+
+    let ref_regex = regex::Regex::new(r"#(\d+)").unwrap();
+
+    for cap in ref_regex.captures_iter(&log) {
+        if let Ok(num) = cap[1].parse::<u32>() {
+            if seen.insert(num) {
+                numbers.push(num);
+            }
+        }
+    }
+
+    Ok(numbers)
+}
+
+/// Parse a `--since` value; a bare date means the start of that day (UTC)
+fn parse_since(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    parse_date(value, NaiveTime::MIN)
+}
+
+/// Parse an `--until` value; a bare date means the end of that day (UTC)
+fn parse_until(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    parse_date(value, NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+}
+
+fn parse_date(value: &str, time_of_day: NaiveTime) -> Result<DateTime<FixedOffset>, String> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime);
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(time_of_day).and_utc().fixed_offset())
+        .map_err(|_| {
+            format!(
+                "invalid date '{}' (expected RFC 3339, e.g. 2024-01-31T12:00:00Z, or YYYY-MM-DD)",
+                value
+            )
+        })
+}
+
+/// Range from the last commit before `since` to HEAD
+///
+/// All of HEAD's history when there is no `since` or no commit before it.
+fn range_since(repo: &Repository, since: Option<DateTime<FixedOffset>>) -> Result<String> {
+    let Some(since) = since else {
+        return Ok("HEAD".to_string());
+    };
+    let output = Command::new("git")
+        .args([
+            "rev-list",
+            "-1",
+            &format!("--before={}", since.to_rfc3339()),
+            "HEAD",
+        ])
+        .current_dir(repo.path().parent().context("Invalid repo path")?)
+        .output()
+        .context("Failed to run git rev-list")?;
+
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || commit.is_empty() {
+        return Ok("HEAD".to_string());
+    }
+    Ok(format!("{}..HEAD", commit))
+}
+
+/// Committer dates (ISO 8601) of the oldest and newest commits in the range
+fn commit_date_bounds(repo: &Repository, range: &str) -> Result<Option<(String, String)>> {
+    let output = Command::new("git")
+        .args(["log", "--format=%cI", range])
+        .current_dir(repo.path().parent().context("Invalid repo path")?)
+        .output()
+        .context("Failed to run git log")?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Invalid revision range: {}", range));
+    }
+
+    let log = String::from_utf8_lossy(&output.stdout);
+    let dates: Vec<&str> = log.lines().filter(|l| !l.is_empty()).collect();
+    // git log lists newest first; ISO dates with mixed offsets don't sort as text
+    match (dates.last(), dates.first()) {
+        (Some(oldest), Some(newest)) => Ok(Some((oldest.to_string(), newest.to_string()))),
+        _ => Ok(None),
+    }
+}
+
+/// Fetch PR details with a search by number (fallback when GraphQL fails)
+fn fetch_pr_details(
+    api: &github::Api,
+    repo_info: &str,
+    pr_numbers: &[u32],
+) -> Result<Vec<PullRequest>> {
     if pr_numbers.is_empty() {
         return Ok(Vec::new());
     }
@@ -211,108 +1030,468 @@ fn fetch_pr_details(repo_info: &str, pr_numbers: &[u32]) -> Result<Vec<PullReque
         .collect::<Vec<_>>()
         .join(" ");
 
-    let output = Command::new("gh")
-        .args([
-            "pr",
-            "list",
-            "--repo",
-            repo_info,
-            "--search",
-            &search_query,
-            "--state",
-            "merged",
-            "--json",
-            "number,title,url,mergedAt,author",
-            "--limit",
-            "1000",
-        ])
-        .output()
-        .context("Failed to run gh command")?;
+    github::search_merged_prs(api, repo_info, &search_query)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("Failed to fetch PR details: {}", stderr));
+/// Sort PRs by the given key; PRs without a merge time sort last either way
+fn sort_pulls(pulls: &mut [PullRequest], key: SortKey, reverse: bool) {
+    let merged_at = |pr: &PullRequest| {
+        pr.merged_at
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+    };
+
+    pulls.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Number => a.number.cmp(&b.number),
+            SortKey::Title => a.title.cmp(&b.title),
+            SortKey::MergedAt => match (merged_at(a), merged_at(b)) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                // Missing merge times are placed last regardless of --reverse
+                (Some(_), None) => return std::cmp::Ordering::Less,
+                (None, Some(_)) => return std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            },
+        };
+        if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// One section per label (alphabetical), used when no sections are configured
+fn label_sections(pulls: &[PullRequest]) -> Vec<(String, Vec<String>)> {
+    let labels: std::collections::BTreeSet<&String> =
+        pulls.iter().flat_map(|pr| &pr.labels).collect();
+    labels
+        .into_iter()
+        .map(|label| (label.clone(), vec![label.clone()]))
+        .collect()
+}
+
+/// Set each PR's section to the first configured section whose labels it
+/// carries, then order PRs by section (uncategorized, then unlabeled last)
+fn assign_sections(pulls: &mut [PullRequest], sections: &[(String, Vec<String>)]) {
+    for pr in pulls.iter_mut() {
+        let section = if pr.labels.is_empty() {
+            UNLABELED_SECTION.to_string()
+        } else {
+            sections
+                .iter()
+                .find(|(_, labels)| labels.iter().any(|l| pr.labels.contains(l)))
+                .map(|(name, _)| name.clone())
+                .unwrap_or_else(|| UNCATEGORIZED_SECTION.to_string())
+        };
+        pr.section = Some(section);
+    }
+
+    let rank = |pr: &PullRequest| {
+        if pr.section.as_deref() == Some(UNLABELED_SECTION) {
+            return sections.len() + 1;
+        }
+        sections
+            .iter()
+            .position(|(name, _)| Some(name) == pr.section.as_ref())
+            .unwrap_or(sections.len())
+    };
+    pulls.sort_by_key(rank);
+}
+
+/// Parse a `--section-map` value of the form `type=Section`
+fn parse_section_mapping(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((commit_type, section)) if !commit_type.is_empty() && !section.is_empty() => Ok((
+            commit_type.trim().to_lowercase(),
+            section.trim().to_string(),
+        )),
+        _ => Err(format!("expected TYPE=SECTION, got '{}'", value)),
     }
+}
 
-    let prs: Vec<serde_json::Value> =
-        serde_json::from_slice(&output.stdout).context("Failed to parse gh output")?;
+/// Default release-notes sections with `--section-map` overrides applied
+///
+/// A remapped type moves out of its default section; sections that only
+/// appear in overrides are added after the defaults.
+fn commit_type_sections(overrides: &[(String, String)]) -> Vec<(String, Vec<String>)> {
+    let mut sections: Vec<(String, Vec<String>)> = COMMIT_TYPE_SECTIONS
+        .iter()
+        .map(|(name, types)| {
+            (
+                name.to_string(),
+                types.iter().map(|t| t.to_string()).collect(),
+            )
+        })
+        .collect();
 
-    let mut pulls = Vec::new();
-    for pr in prs {
-        if let Some(number) = pr["number"].as_u64() {
-            pulls.push(PullRequest {
-                number: number as u32,
-                title: pr["title"].as_str().unwrap_or("").to_string(),
-                url: pr["url"].as_str().unwrap_or("").to_string(),
-                merged_at: pr["mergedAt"].as_str().map(|s| s.to_string()),
-                author: pr["author"]["login"].as_str().map(|s| s.to_string()),
-            });
+    for (commit_type, section) in overrides {
+        for (_, types) in sections.iter_mut() {
+            types.retain(|t| t != commit_type);
+        }
+        match sections.iter_mut().find(|(name, _)| name == section) {
+            Some((_, types)) => types.push(commit_type.clone()),
+            None => sections.push((section.clone(), vec![commit_type.clone()])),
         }
     }
+    sections
+}
 
-    // Warn about missing PRs
-    let fetched_numbers: std::collections::HashSet<u32> = pulls.iter().map(|p| p.number).collect();
-    for &num in pr_numbers {
-        if !fetched_numbers.contains(&num) {
-            eprintln!("Warning: PR #{} not found or not merged", num);
+/// Conventional Commits type of a subject like `feat(cli)!: add flag`
+fn commit_type(subject: &str) -> Option<String> {
+    let (prefix, _) = subject.split_once(':')?;
+    let prefix = prefix.trim_end_matches('!');
+    let commit_type = match prefix.split_once('(') {
+        Some((commit_type, scope)) if scope.ends_with(')') => commit_type,
+        Some(_) => return None,
+        None => prefix,
+    };
+    if commit_type.is_empty() || !commit_type.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(commit_type.to_lowercase())
+}
+
+/// Subject of a PR's merge commit, if it exists locally
+fn merge_commit_subject(repo: &Repository, pr: &PullRequest) -> Option<String> {
+    let oid = git2::Oid::from_str(pr.merge_commit.as_deref()?).ok()?;
+    let commit = repo.find_commit(oid).ok()?;
+    commit.summary().map(|s| s.to_string())
+}
+
+/// Set each PR's section from the Conventional Commits type of its title (or
+/// merge commit subject), then order PRs by section with "Other" last
+fn assign_commit_type_sections(
+    pulls: &mut [PullRequest],
+    sections: &[(String, Vec<String>)],
+    merge_subject: impl Fn(&PullRequest) -> Option<String>,
+) {
+    for pr in pulls.iter_mut() {
+        let commit_type =
+            commit_type(&pr.title).or_else(|| merge_subject(pr).as_deref().and_then(commit_type));
+        let section = commit_type
+            .and_then(|t| sections.iter().find(|(_, types)| types.contains(&t)))
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| OTHER_SECTION.to_string());
+        pr.section = Some(section);
+    }
+
+    let rank = |pr: &PullRequest| {
+        sections
+            .iter()
+            .position(|(name, _)| Some(name) == pr.section.as_ref())
+            .unwrap_or(sections.len())
+    };
+    pulls.sort_by_key(rank);
+}
+
+/// Shorten a PR body to at most `limit` characters, marking the cut with "…"
+fn truncate_body(body: &str, limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) if body.chars().count() > limit => {
+            let truncated: String = body.chars().take(limit).collect();
+            format!("{}…", truncated.trim_end())
         }
+        _ => body.to_string(),
     }
+}
 
-    Ok(pulls)
+/// Number of PRs and PRs per author (most PRs first, then by name)
+fn pr_stats(pulls: &[PullRequest]) -> (usize, Vec<(&str, usize)>) {
+    let mut counts: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    for pr in pulls {
+        *counts
+            .entry(pr.author.as_deref().unwrap_or("unknown"))
+            .or_default() += 1;
+    }
+    let mut authors: Vec<(&str, usize)> = counts.into_iter().collect();
+    authors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    (pulls.len(), authors)
+}
+
+fn print_stats(out: &mut dyn Write, pulls: &[PullRequest], format: OutputFormat) -> Result<()> {
+    let (total, authors) = pr_stats(pulls);
+    let summary = format!(
+        "{} PR{} from {} author{}",
+        total,
+        if total == 1 { "" } else { "s" },
+        authors.len(),
+        if authors.len() == 1 { "" } else { "s" }
+    );
+
+    writeln!(out)?;
+    if let OutputFormat::Markdown | OutputFormat::ReleaseNotes | OutputFormat::Table = format {
+        let breakdown = authors
+            .iter()
+            .map(|(author, count)| format!("@{} ({})", author, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if breakdown.is_empty() {
+            writeln!(out, "**{}**", summary)?;
+        } else {
+            writeln!(out, "**{}**: {}", summary, breakdown)?;
+        }
+    } else {
+        writeln!(out, "{}", summary)?;
+        for (author, count) in authors {
+            writeln!(out, "  {}: {}", author, count)?;
+        }
+    }
+    Ok(())
 }
 
-fn print_text(output: &Output, with_links: bool) {
-    for pr in &output.pulls {
+/// Split PRs into consecutive runs sharing the same section
+fn section_groups(pulls: &[PullRequest]) -> Vec<(Option<&str>, &[PullRequest])> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    for i in 1..=pulls.len() {
+        if i == pulls.len() || pulls[i].section != pulls[start].section {
+            groups.push((pulls[start].section.as_deref(), &pulls[start..i]));
+            start = i;
+        }
+    }
+    groups
+}
+
+fn print_text(
+    out: &mut dyn Write,
+    output: &Output,
+    with_links: bool,
+    with_range: bool,
+) -> Result<()> {
+    if with_range {
+        writeln!(out, "{}", output.range)?;
+    }
+    for (i, (section, pulls)) in section_groups(&output.pulls).into_iter().enumerate() {
+        if let Some(section) = section {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "{}:", section)?;
+        }
+        print_text_pulls(out, pulls, &output.platform, with_links)?;
+    }
+
+    if !output.issues.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "Closed issues:")?;
+        for issue in &output.issues {
+            let number = format!("#{}", issue.number);
+            if with_links {
+                writeln!(
+                    out,
+                    "{} {}",
+                    term::osc8_link(&issue.url, &number),
+                    issue.title
+                )?;
+            } else {
+                writeln!(out, "{} {}", number, issue.title)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_text_pulls(
+    out: &mut dyn Write,
+    pulls: &[PullRequest],
+    platform: &str,
+    with_links: bool,
+) -> Result<()> {
+    let prefix = pr_prefix(platform);
+    for pr in pulls {
+        let number = format!("{}{}", prefix, pr.number);
         if with_links {
-            // OSC 8 format: \x1b]8;;URL\x1b\\TEXT\x1b]8;;\x1b\\
-            print!("\x1b]8;;{}\x1b\\", pr.url);
-            print!("#{}", pr.number);
-            print!("\x1b]8;;\x1b\\");
-            println!();
+            writeln!(out, "{}", term::osc8_link(&pr.url, &number))?;
         } else {
-            println!("#{}", pr.number);
+            writeln!(out, "{}", number)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prefix for PR/MR numbers in output (`!` for GitLab merge requests)
+fn pr_prefix(platform: &str) -> &'static str {
+    if platform == Platform::GitLab.as_str() {
+        Platform::GitLab.ref_prefix()
+    } else {
+        Platform::GitHub.ref_prefix()
+    }
+}
+
+fn print_json(out: &mut dyn Write, output: &impl Serialize, pretty: bool) -> Result<()> {
+    if pretty {
+        writeln!(out, "{}", serde_json::to_string_pretty(output)?)?;
+    } else {
+        writeln!(out, "{}", serde_json::to_string(output)?)?;
+    }
+    Ok(())
+}
+
+fn print_markdown(out: &mut dyn Write, output: &Output) -> Result<()> {
+    let prefix = pr_prefix(&output.platform);
+    writeln!(out, "## Merged PRs ({})", output.range)?;
+    writeln!(out)?;
+    for (i, (section, pulls)) in section_groups(&output.pulls).into_iter().enumerate() {
+        if let Some(section) = section {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "### {}", section)?;
+            writeln!(out)?;
+        }
+        for pr in pulls {
+            write!(out, "- [{}{}]({}) {}", prefix, pr.number, pr.url, pr.title)?;
+            if let Some(author) = &pr.author {
+                write!(out, " (@{})", author)?;
+            }
+            writeln!(out)?;
+            // Indent the body so it stays part of the list item
+            if let Some(body) = &pr.body {
+                writeln!(out)?;
+                for line in body.lines() {
+                    if line.trim().is_empty() {
+                        writeln!(out)?;
+                    } else {
+                        writeln!(out, "  {}", line)?;
+                    }
+                }
+                writeln!(out)?;
+            }
         }
     }
+
+    if !output.issues.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "## Closed issues")?;
+        writeln!(out)?;
+        for issue in &output.issues {
+            writeln!(out, "- [#{}]({}) {}", issue.number, issue.url, issue.title)?;
+        }
+    }
+    Ok(())
 }
 
-fn print_json(output: &Output) -> Result<()> {
-    println!("{}", serde_json::to_string_pretty(output)?);
+fn print_table(out: &mut dyn Write, output: &Output) -> Result<()> {
+    let prefix = pr_prefix(&output.platform);
+    writeln!(out, "## Merged PRs ({})", output.range)?;
+    writeln!(out)?;
+    for (i, (section, pulls)) in section_groups(&output.pulls).into_iter().enumerate() {
+        if let Some(section) = section {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "### {}", section)?;
+            writeln!(out)?;
+        }
+        writeln!(out, "| # | Title | Author | Merged |")?;
+        writeln!(out, "|--:|-------|--------|--------|")?;
+        for pr in pulls {
+            writeln!(
+                out,
+                "| [{}{}]({}) | {} | {} | {} |",
+                prefix,
+                pr.number,
+                pr.url,
+                escape_table_cell(&pr.title),
+                pr.author
+                    .as_deref()
+                    .map(|author| format!("@{}", escape_table_cell(author)))
+                    .unwrap_or_default(),
+                // Date part of the ISO 8601 timestamp
+                pr.merged_at
+                    .as_deref()
+                    .map(|merged_at| merged_at.get(..10).unwrap_or(merged_at))
+                    .unwrap_or_default(),
+            )?;
+        }
+    }
+
+    if !output.issues.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "## Closed issues")?;
+        writeln!(out)?;
+        writeln!(out, "| # | Title |")?;
+        writeln!(out, "|--:|-------|")?;
+        for issue in &output.issues {
+            writeln!(
+                out,
+                "| [#{}]({}) | {} |",
+                issue.number,
+                issue.url,
+                escape_table_cell(&issue.title)
+            )?;
+        }
+    }
     Ok(())
 }
 
-fn print_markdown(output: &Output) {
-    println!("## Merged PRs ({})", output.range);
-    println!();
-    for pr in &output.pulls {
-        print!("- [#{}]({}) {}", pr.number, pr.url, pr.title);
-        if let Some(author) = &pr.author {
-            print!(" (@{})", author);
+/// Escape text for a GFM table cell (pipes split cells, newlines end rows)
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// URL listing the merged PRs on the hosting platform
+///
+/// GitLab's merge request list can't filter by number, so it shows all merged MRs.
+fn web_url(remote: &RemoteRepo, pr_numbers: &[u32]) -> String {
+    match remote.platform {
+        Platform::GitHub => {
+            let query = pr_numbers
+                .iter()
+                .map(|n| format!("%23{}", n))
+                .collect::<Vec<_>>()
+                .join("+");
+            format!(
+                "https://{}/{}/pulls?q=is:pr+is:merged+{}",
+                remote.host, remote.path, query
+            )
         }
-        println!();
+        Platform::GitLab => format!(
+            "https://{}/{}/-/merge_requests?scope=all&state=merged",
+            remote.host, remote.path
+        ),
     }
 }
 
-fn open_in_browser(repo_info: &str, pr_numbers: &[u32]) -> Result<()> {
-    let query = pr_numbers
-        .iter()
-        .map(|n| format!("%23{}", n))
-        .collect::<Vec<_>>()
-        .join("+");
-    let url = format!(
-        "https://github.com/{}/pulls?q=is:pr+is:merged+{}",
-        repo_info, query
-    );
+/// Open each PR's page, asking first when that means many tabs
+fn open_all_in_browser(pulls: &[PullRequest], max_open: Option<usize>) -> Result<()> {
+    let count = max_open.map_or(pulls.len(), |max| pulls.len().min(max));
+    if count == 0 {
+        println!("No pull requests to open");
+        return Ok(());
+    }
 
+    if count > OPEN_ALL_CONFIRM_THRESHOLD {
+        let confirmed = Confirm::new(&format!("Open {} pull requests in the browser?", count))
+            .with_default(false)
+            .prompt()?;
+        if !confirmed {
+            return Ok(());
+        }
+    }
+
+    for pr in &pulls[..count] {
+        open_in_browser(&pr.url)?;
+    }
+    if count < pulls.len() {
+        println!(
+            "Opened {} of {} pull requests (--max-open)",
+            count,
+            pulls.len()
+        );
+    }
+    Ok(())
+}
+
+fn open_in_browser(url: &str) -> Result<()> {
     // Determine the appropriate command based on the platform
     let (cmd, args) = if cfg!(target_os = "macos") {
-        ("open", vec![url.as_str()])
+        ("open", vec![url])
     } else if cfg!(target_os = "linux") {
-        ("xdg-open", vec![url.as_str()])
+        ("xdg-open", vec![url])
     } else if cfg!(target_os = "windows") {
-        (
-            "rundll32",
-            vec!["url.dll,FileProtocolHandler", url.as_str()],
-        )
+        ("rundll32", vec!["url.dll,FileProtocolHandler", url])
     } else {
         eprintln!("Warning: Unsupported platform for auto-opening browser");
         println!("URL: {}", url);
@@ -345,3 +1524,381 @@ fn open_in_browser(repo_info: &str, pr_numbers: &[u32]) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use tempfile::TempDir;
+
+    fn commit(repo: &Repository, message: &str) -> git2::Oid {
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_extract_pr_numbers_excludes_lower_bound() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        commit(&repo, "Initial commit");
+        let a = commit(&repo, "Merge pull request #1 from poi2/feature-a");
+        commit(&repo, "Add feature (#2)");
+        let b = commit(&repo, "Merge pull request #3 from poi2/feature-b");
+
+        let range = format!("{}..{}", a, b);
+        let pr_numbers = extract_pr_numbers(&repo, &range, Platform::GitHub, None).unwrap();
+        assert_eq!(pr_numbers, vec![3, 2]);
+
+        // Only trailing "(#N)" references
+        let pattern = compile_pr_pattern(r"\(#(\d+)\)$").unwrap();
+        let pr_numbers =
+            extract_pr_numbers(&repo, &range, Platform::GitHub, Some(&pattern)).unwrap();
+        assert_eq!(pr_numbers, vec![2]);
+
+        assert!(compile_pr_pattern(r"#\d+").is_err());
+    }
+
+    #[test]
+    fn test_extract_gitlab_merge_requests() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        let a = commit(&repo, "Initial commit");
+        let b = commit(
+            &repo,
+            "Merge branch 'feature' into 'main'\n\nAdd feature\n\nSee merge request group/project!42",
+        );
+
+        let range = format!("{}..{}", a, b);
+        let mr_numbers = extract_pr_numbers(&repo, &range, Platform::GitLab, None).unwrap();
+        assert_eq!(mr_numbers, vec![42]);
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        assert_eq!(
+            parse_since("2024-01-31").unwrap().to_rfc3339(),
+            "2024-01-31T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_until("2024-01-31").unwrap().to_rfc3339(),
+            "2024-01-31T23:59:59+00:00"
+        );
+        assert_eq!(
+            parse_since("2024-01-31T12:00:00+09:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-01-31T12:00:00+09:00"
+        );
+        assert!(parse_since("31/01/2024").is_err());
+        assert!(parse_until("2024-13-01").is_err());
+    }
+
+    #[test]
+    fn test_web_url_filters_by_pr_number() {
+        let remote = RemoteRepo {
+            name: "origin".to_string(),
+            platform: Platform::GitHub,
+            host: "github.com".to_string(),
+            path: "poi2/git-utils".to_string(),
+        };
+        assert_eq!(
+            web_url(&remote, &[12, 34]),
+            "https://github.com/poi2/git-utils/pulls?q=is:pr+is:merged+%2312+%2334"
+        );
+    }
+
+    #[test]
+    fn test_other_remotes_skips_chosen_and_duplicates() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        repo.remote("origin", "git@github.com:me/project.git")
+            .unwrap();
+        repo.remote("mirror", "https://github.com/me/project.git")
+            .unwrap();
+        repo.remote("upstream", "https://github.com/org/project.git")
+            .unwrap();
+
+        let origin = get_repo_info(&repo, "origin").unwrap();
+        let others = other_remotes(&repo, &origin).unwrap();
+        let names: Vec<_> = others
+            .iter()
+            .map(|r| (r.name.as_str(), r.path.as_str()))
+            .collect();
+        assert_eq!(names, vec![("upstream", "org/project")]);
+    }
+
+    #[test]
+    fn test_extract_numbers_with_invalid_utf8_message() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let parent = commit(&repo, "Initial commit");
+
+        // git2 only accepts &str messages, so write the commit object by hand
+        let tree = repo.find_commit(parent).unwrap().tree_id();
+        let mut raw = format!(
+            "tree {}\nparent {}\nauthor test <test@example.com> 0 +0000\ncommitter test <test@example.com> 0 +0000\n\n",
+            tree, parent
+        )
+        .into_bytes();
+        raw.extend_from_slice(b"Caf\xe9 fix (#7)\n\nRefs #8\n");
+        let oid = repo
+            .odb()
+            .unwrap()
+            .write(git2::ObjectType::Commit, &raw)
+            .unwrap();
+        repo.reference("refs/heads/latin1", oid, true, "test")
+            .unwrap();
+
+        let range = format!("{}..latin1", parent);
+        assert_eq!(
+            extract_pr_numbers(&repo, &range, Platform::GitHub, None).unwrap(),
+            vec![7]
+        );
+        assert_eq!(extract_issue_numbers(&repo, &range).unwrap(), vec![7, 8]);
+        assert!(commit_date_bounds(&repo, &range).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_default_range() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        let base = commit(&repo, "Initial commit");
+        repo.reference("refs/remotes/origin/trunk", base, true, "test")
+            .unwrap();
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/trunk",
+            true,
+            "test",
+        )
+        .unwrap();
+
+        // HEAD is still on the default branch
+        assert_eq!(default_range(&repo), "HEAD~10..HEAD");
+
+        commit(&repo, "Add feature (#1)");
+        assert_eq!(default_range(&repo), "origin/trunk..HEAD");
+
+        // The latest tag still comes first
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.tag_lightweight("v1.0.0", head.as_object(), false)
+            .unwrap();
+        assert_eq!(default_range(&repo), "v1.0.0..HEAD");
+    }
+
+    #[test]
+    fn test_resolve_remote_falls_back_to_recognized_remote() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        repo.remote("origin", "git@git.example.com:me/project.git")
+            .unwrap();
+        repo.remote("github", "https://github.com/me/project.git")
+            .unwrap();
+
+        let remote = resolve_remote(&repo, None).unwrap();
+        assert_eq!(remote.name, "github");
+        assert!(matches!(remote.platform, Platform::GitHub));
+
+        // An explicit --remote is never replaced
+        assert!(resolve_remote(&repo, Some("origin")).is_err());
+    }
+
+    #[test]
+    fn test_parse_remote_url() {
+        let expected = Some((
+            "gitlab.example.com".to_string(),
+            "group/sub/project".to_string(),
+        ));
+        assert_eq!(
+            parse_remote_url("git@gitlab.example.com:group/sub/project.git"),
+            expected
+        );
+        assert_eq!(
+            parse_remote_url("https://gitlab.example.com/group/sub/project.git"),
+            expected
+        );
+        assert_eq!(
+            parse_remote_url("ssh://git@gitlab.example.com:2222/group/sub/project.git"),
+            expected
+        );
+    }
+
+    fn pr(number: u32, labels: &[&str]) -> PullRequest {
+        PullRequest {
+            number,
+            title: String::new(),
+            url: String::new(),
+            merged_at: None,
+            author: None,
+            base_ref: None,
+            merge_commit: None,
+            merged_by: None,
+            merge_method: None,
+            is_draft: false,
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            body: None,
+            section: None,
+        }
+    }
+
+    #[test]
+    fn test_sort_pulls_by_merged_at() {
+        let mut pulls = vec![pr(1, &[]), pr(2, &[]), pr(3, &[])];
+        pulls[0].merged_at = Some("2024-02-01T00:00:00Z".to_string());
+        pulls[2].merged_at = Some("2024-01-01T00:00:00Z".to_string());
+
+        sort_pulls(&mut pulls, SortKey::MergedAt, false);
+        let numbers: Vec<_> = pulls.iter().map(|p| p.number).collect();
+        assert_eq!(numbers, vec![3, 1, 2]);
+
+        sort_pulls(&mut pulls, SortKey::MergedAt, true);
+        let numbers: Vec<_> = pulls.iter().map(|p| p.number).collect();
+        assert_eq!(numbers, vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_assign_commit_type_sections() {
+        let mut pulls = vec![
+            pr(1, &[]),
+            pr(2, &[]),
+            pr(3, &[]),
+            pr(4, &[]),
+            pr(5, &[]),
+            pr(6, &[]),
+        ];
+        pulls[0].title = "Update README".to_string();
+        pulls[1].title = "fix(cli)!: handle empty ranges".to_string();
+        pulls[2].title = "feat: add --sort".to_string();
+        pulls[3].title = "chore: bump deps".to_string();
+        pulls[4].title = "perf: cache lookups".to_string();
+        pulls[5].title = "Merge pull request #6".to_string();
+        pulls[5].merge_commit = Some("merge".to_string());
+
+        let sections = commit_type_sections(&[("perf".to_string(), "Performance".to_string())]);
+        assign_commit_type_sections(&mut pulls, &sections, |pr| {
+            pr.merge_commit
+                .as_ref()
+                .map(|_| "feat: from merge commit".to_string())
+        });
+
+        let result: Vec<_> = pulls
+            .iter()
+            .map(|p| (p.number, p.section.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                (3, "Features"),
+                (6, "Features"),
+                (2, "Bug Fixes"),
+                (4, "Maintenance"),
+                (5, "Performance"),
+                (1, OTHER_SECTION),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_print_table() {
+        let mut with_pipe = pr(1, &[]);
+        with_pipe.title = "Support a|b syntax".to_string();
+        with_pipe.url = "https://github.com/o/r/pull/1".to_string();
+        with_pipe.author = Some("alice".to_string());
+        with_pipe.merged_at = Some("2024-02-01T10:00:00Z".to_string());
+        let output = Output {
+            range: "v1.0.0..HEAD".to_string(),
+            platform: Platform::GitHub.as_str().to_string(),
+            pulls: vec![with_pipe, pr(2, &[])],
+            issues: Vec::new(),
+        };
+
+        let mut buf = Vec::new();
+        print_table(&mut buf, &output).unwrap();
+        let table = String::from_utf8(buf).unwrap();
+        let rows: Vec<&str> = table.lines().skip(2).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "| # | Title | Author | Merged |",
+                "|--:|-------|--------|--------|",
+                r"| [#1](https://github.com/o/r/pull/1) | Support a\|b syntax | @alice | 2024-02-01 |",
+                "| [#2]() |  |  |  |",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate_body() {
+        assert_eq!(truncate_body("short", Some(10)), "short");
+        assert_eq!(truncate_body("a long body", Some(6)), "a long…");
+        assert_eq!(truncate_body("ääää", Some(2)), "ää…");
+        assert_eq!(truncate_body("untouched", None), "untouched");
+    }
+
+    #[test]
+    fn test_pr_stats() {
+        let mut pulls = vec![pr(1, &[]), pr(2, &[]), pr(3, &[]), pr(4, &[])];
+        pulls[0].author = Some("bob".to_string());
+        pulls[1].author = Some("alice".to_string());
+        pulls[2].author = Some("bob".to_string());
+        pulls[3].author = Some("carol".to_string());
+
+        let (total, authors) = pr_stats(&pulls);
+        assert_eq!(total, 4);
+        assert_eq!(authors, vec![("bob", 2), ("alice", 1), ("carol", 1)]);
+    }
+
+    #[test]
+    fn test_tag_ranges() {
+        let tags: Vec<String> = ["v1.0.0", "v1.1.0", "v2.0.0"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(
+            tag_ranges(&tags, None),
+            vec!["v1.1.0..v2.0.0", "v1.0.0..v1.1.0", "v1.0.0"]
+        );
+        assert_eq!(tag_ranges(&tags, Some(1)), vec!["v1.1.0..v2.0.0"]);
+    }
+
+    #[test]
+    fn test_assign_sections() {
+        let sections = vec![
+            ("Features".to_string(), vec!["feature".to_string()]),
+            (
+                "Fixes".to_string(),
+                vec!["bug".to_string(), "fix".to_string()],
+            ),
+        ];
+        let mut pulls = vec![
+            pr(4, &[]),
+            pr(1, &["docs"]),
+            pr(2, &["fix"]),
+            pr(3, &["bug", "feature"]),
+        ];
+
+        assign_sections(&mut pulls, &sections);
+
+        let result: Vec<_> = pulls
+            .iter()
+            .map(|p| (p.number, p.section.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                (3, "Features"),
+                (2, "Fixes"),
+                (1, UNCATEGORIZED_SECTION),
+                (4, UNLABELED_SECTION)
+            ]
+        );
+    }
+}
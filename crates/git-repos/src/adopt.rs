@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use git2::Repository;
+use inquire::Confirm;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::utils::{get_repo_root, parse_repo_url, Verbosity};
+
+#[derive(Args)]
+pub struct AdoptArgs {
+    /// Path to an existing repository
+    path: PathBuf,
+
+    /// Move without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+pub fn adopt_repo(args: &AdoptArgs, verbosity: Verbosity) -> Result<()> {
+    let repo = Repository::open(&args.path)
+        .with_context(|| format!("Not a git repository: {}", args.path.display()))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("Bare repositories cannot be adopted"))?
+        .canonicalize()?;
+
+    let remote = repo
+        .find_remote("origin")
+        .context("No 'origin' remote found")?;
+    let url = remote.url().context("Invalid remote URL")?;
+    let info = parse_repo_url(url)?;
+
+    // Canonical layout: <root>/<domain>/<user>/<repo>
+    let repo_root = get_repo_root(None)?;
+    let target_path = repo_root
+        .join(&info.domain)
+        .join(&info.user)
+        .join(&info.repo);
+
+    if target_path.exists() {
+        if target_path.canonicalize()? == workdir {
+            if !verbosity.is_quiet() {
+                println!("Already in place: {}", target_path.display());
+            }
+            return Ok(());
+        }
+        return Err(anyhow!(
+            "Target path already exists: {}",
+            target_path.display()
+        ));
+    }
+
+    if !args.yes {
+        let answer = Confirm::new(&format!(
+            "Move {} to {}?",
+            workdir.display(),
+            target_path.display()
+        ))
+        .with_default(false)
+        .prompt()?;
+
+        if !answer {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    if let Some(parent) = target_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&workdir, &target_path).with_context(|| {
+        format!(
+            "Failed to move {} to {}",
+            workdir.display(),
+            target_path.display()
+        )
+    })?;
+
+    if verbosity.is_quiet() {
+        println!("{}", target_path.display());
+    } else {
+        println!("Moved {} to {}", workdir.display(), target_path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, ValueEnum};
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::ls::select_repo;
+use crate::utils::{get_repo_root, is_repo_dir, repo_path_under_root, Verbosity};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ArchiveFormat {
+    #[value(name = "tar")]
+    Tar,
+    #[value(name = "tar.gz")]
+    TarGz,
+    #[value(name = "zip")]
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ArchiveArgs {
+    /// Repository path relative to the root (e.g. github.com/user/repo); selected interactively if omitted
+    repo: Option<String>,
+
+    /// Archive format
+    #[arg(long, value_enum, default_value = "tar.gz")]
+    format: ArchiveFormat,
+
+    /// Revision to archive
+    #[arg(long = "ref", value_name = "REV", default_value = "HEAD")]
+    rev: String,
+
+    /// Output file (writes to stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn archive_repo(args: &ArchiveArgs, verbosity: Verbosity) -> Result<()> {
+    let repo_root = get_repo_root(None)?;
+
+    let relative_path = match &args.repo {
+        Some(repo) => repo.clone(),
+        None => select_repo(&repo_root, "Select a repository to archive:", None, None)?,
+    };
+
+    let repo_path = repo_path_under_root(&repo_root, &relative_path)?;
+    if !is_repo_dir(&repo_path) {
+        return Err(anyhow!("Not a managed repository: {}", repo_path.display()));
+    }
+
+    let repo_name = repo_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+
+    let mut cmd = Command::new("git");
+    cmd.arg("-C")
+        .arg(&repo_path)
+        .arg("archive")
+        .arg(format!("--format={}", args.format.as_str()))
+        .arg(format!("--prefix={}/", repo_name));
+
+    if let Some(output) = &args.output {
+        cmd.arg("-o").arg(output);
+    }
+
+    cmd.arg(&args.rev);
+
+    let status = cmd.status().context("Failed to run git archive")?;
+    if !status.success() {
+        return Err(anyhow!("git archive failed for {}", repo_path.display()));
+    }
+
+    if let Some(output) = &args.output {
+        if !verbosity.is_quiet() {
+            println!(
+                "Archived {} ({}) to {}",
+                relative_path,
+                args.rev,
+                output.display()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use std::path::Path;
+
+use crate::ls::{repo_preview, select_repo};
+use crate::utils::{get_repo_root, Verbosity};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Args)]
+pub struct CdArgs {
+    /// Only offer repositories whose path contains this pattern
+    pattern: Option<String>,
+
+    /// Print a `cd` command for the given shell, for use with eval
+    #[arg(long, value_enum, value_name = "SHELL")]
+    exec: Option<Shell>,
+
+    /// Command whose first output line is shown for the highlighted repository; `{}` is
+    /// replaced by the repository path (default: age and subject of the last commit)
+    #[arg(long, value_name = "CMD")]
+    preview_command: Option<String>,
+}
+
+pub fn cd_repo(args: &CdArgs, verbosity: Verbosity) -> Result<()> {
+    let repo_root = get_repo_root(None)?;
+    let preview = |path: &Path| repo_preview(args.preview_command.as_deref(), path);
+    let relative_path = select_repo(
+        &repo_root,
+        "Select a repository:",
+        args.pattern.as_deref(),
+        Some(&preview),
+    )?;
+    let repo_path = repo_root.join(&relative_path);
+    let path = repo_path.to_string_lossy();
+
+    if verbosity.is_verbose() {
+        eprintln!("Selected: {}", relative_path);
+    }
+
+    match args.exec {
+        Some(Shell::Bash | Shell::Zsh) => println!("cd {}", quote_posix(&path)),
+        Some(Shell::Fish) => println!("cd {}", quote_fish(&path)),
+        None => println!("{}", path),
+    }
+
+    Ok(())
+}
+
+/// Single-quote a string for bash/zsh
+pub(crate) fn quote_posix(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Single-quote a string for fish, where `\` and `'` are escaped inside quotes
+fn quote_fish(s: &str) -> String {
+    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_paths() {
+        assert_eq!(quote_posix("/src/it's"), r"'/src/it'\''s'");
+        assert_eq!(quote_fish(r"/src/it's\x"), r"'/src/it\'s\\x'");
+    }
+}
@@ -1,65 +1,933 @@
-use anyhow::Result;
-use git2::{build::RepoBuilder, Cred, FetchOptions, RemoteCallbacks};
+use anyhow::{anyhow, Result};
+use clap::Args;
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{BranchType, FetchOptions, Repository, SubmoduleUpdateOptions};
+use git_utils_core::auth;
+use git_utils_core::config::Config;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use inquire::validator::Validation;
+use inquire::{Confirm, Select, Text};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
 
-use crate::utils::{convert_url_if_needed, get_repo_root, parse_repo_url};
+use crate::utils::{convert_url_if_needed, get_repo_root, parse_repo_url, Verbosity};
 
-pub fn clone_repo(url: &str, shallow: bool, bare: bool, branch: Option<&str>) -> Result<()> {
-    let repo_root = get_repo_root()?;
+#[derive(Args)]
+pub struct CloneArgs {
+    /// Repository URL
+    #[arg(required_unless_present = "from_file")]
+    url: Option<String>,
+
+    /// Clone every URL listed in FILE (one per line; blank lines and `#` comments
+    /// are ignored), continuing past failures and printing a summary at the end
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["url", "name", "open", "print_path"]
+    )]
+    from_file: Option<PathBuf>,
+
+    /// Shallow clone with --depth=1
+    #[arg(long)]
+    shallow: bool,
+
+    /// Shallow clone with history truncated to N commits
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(i32).range(1..),
+        conflicts_with = "shallow"
+    )]
+    depth: Option<i32>,
+
+    /// Clone full history, ignoring git-repos.clone.shallow/depth
+    #[arg(long, conflicts_with_all = ["shallow", "depth"])]
+    no_shallow: bool,
+
+    /// Clone as bare repository
+    #[arg(long)]
+    bare: bool,
+
+    /// Checkout specific branch
+    #[arg(short, long)]
+    branch: Option<String>,
+
+    /// Fetch only the branch given by --branch
+    #[arg(long, requires = "branch")]
+    single_branch: bool,
+
+    /// Fetch all branches, ignoring git-repos.clone.single-branch
+    #[arg(long, conflicts_with = "single_branch")]
+    no_single_branch: bool,
+
+    /// Number of retries on transient network failures
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    retries: u32,
+
+    /// Copy files from this directory into the clone (existing files are kept)
+    #[arg(long, value_name = "DIR", conflicts_with = "bare")]
+    template: Option<PathBuf>,
+
+    /// Open the cloned repository in an editor ($VISUAL, then $EDITOR)
+    #[arg(long)]
+    open: bool,
+
+    /// Editor command to use with --open
+    #[arg(long, value_name = "CMD", requires = "open")]
+    editor: Option<String>,
+
+    /// Print only the cloned path to stdout (other messages go to stderr)
+    #[arg(long)]
+    print_path: bool,
+
+    /// Directory name for the clone instead of the repository name (e.g. for gists)
+    #[arg(long = "as", value_name = "NAME")]
+    name: Option<String>,
+
+    /// Use this repository root instead of the configured one
+    #[arg(long, value_name = "PATH")]
+    root: Option<String>,
+
+    /// What to do when the target directory already exists (default: ask, or skip when not a TTY)
+    #[arg(long, value_enum, value_name = "ACTION")]
+    on_exists: Option<OnExists>,
+
+    /// Exit with an error when the target already exists, instead of asking or skipping
+    #[arg(long, conflicts_with = "on_exists")]
+    fail_on_exists: bool,
+
+    /// Also clone submodules, recursively
+    #[arg(long, visible_alias = "recursive", conflicts_with = "bare")]
+    recurse_submodules: bool,
+
+    /// Number of submodules to fetch in parallel, like `git clone --jobs`
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        requires = "recurse_submodules",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    jobs: Option<u32>,
+
+    /// Number of repositories from --from-file to clone in parallel
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "from_file",
+        conflicts_with = "url",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    parallel: Option<u32>,
+}
+
+/// Clone settings after applying `git-repos.clone.*` defaults
+///
+/// `shallow`, `depth` and `single-branch` in config apply unless a flag says
+/// otherwise; `--no-shallow` and `--no-single-branch` turn them off.
+#[derive(Debug)]
+struct CloneOptions {
+    depth: Option<i32>,
+    single_branch: bool,
+}
+
+impl CloneOptions {
+    fn resolve(args: &CloneArgs, config: &Config) -> Result<Self> {
+        let depth = if args.no_shallow {
+            None
+        } else if args.depth.is_some() {
+            args.depth
+        } else if args.shallow {
+            Some(1)
+        } else if let Some(depth) = config.get_int("git-repos.clone.depth") {
+            match i32::try_from(depth) {
+                Ok(depth) if depth > 0 => Some(depth),
+                _ => return Err(anyhow!("Invalid git-repos.clone.depth: {}", depth)),
+            }
+        } else if config.get_bool("git-repos.clone.shallow") == Some(true) {
+            Some(1)
+        } else {
+            None
+        };
+
+        // Restricting the fetch needs a branch name, so the default only
+        // applies together with --branch
+        let single_branch = args.single_branch
+            || (args.branch.is_some()
+                && !args.no_single_branch
+                && config.get_bool("git-repos.clone.single-branch") == Some(true));
+
+        Ok(Self {
+            depth,
+            single_branch,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum OnExists {
+    /// Keep the existing directory and succeed without cloning
+    Skip,
+    /// Fetch origin in the existing clone and fast-forward the current branch
+    Update,
+    /// Clone again, replacing the existing directory once the clone succeeds
+    Replace,
+    /// Clone next to it under a free name (<repo>-2, <repo>-3, ...)
+    Rename,
+    /// Exit with an error
+    Fail,
+}
+
+pub fn clone_repo(args: &CloneArgs, verbosity: Verbosity) -> Result<()> {
+    if let Some(list) = &args.from_file {
+        return clone_from_file(args, list, verbosity);
+    }
+    let url = args
+        .url
+        .as_deref()
+        .ok_or_else(|| anyhow!("A repository URL is required"))?;
+    clone_url(args, url, false, verbosity)
+}
+
+/// Clone every URL listed in a file, `--parallel` at a time
+///
+/// A failed clone doesn't stop the others; failures are listed in the summary
+/// and make the command exit with an error.
+fn clone_from_file(args: &CloneArgs, list: &Path, verbosity: Verbosity) -> Result<()> {
+    let content = fs::read_to_string(list)
+        .map_err(|e| anyhow!("Failed to read {}: {}", list.display(), e))?;
+    let urls: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    if urls.is_empty() {
+        return Err(anyhow!("No repository URLs in {}", list.display()));
+    }
+
+    let jobs = args.parallel.unwrap_or(1) as usize;
+    let next = AtomicUsize::new(0);
+    let failed = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(urls.len()) {
+            scope.spawn(|| {
+                while let Some(&url) = urls.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(e) = clone_url(args, url, true, verbosity) {
+                        eprintln!("Failed to clone {}: {}", url, e);
+                        failed.lock().unwrap().push(url);
+                    }
+                }
+            });
+        }
+    });
+
+    // Report failures in the order they appear in the file
+    let mut failed = failed.into_inner().unwrap();
+    failed.sort_by_key(|url| urls.iter().position(|u| u == url));
+    if !verbosity.is_quiet() {
+        println!();
+        println!(
+            "{} of {} repositories cloned or already present",
+            urls.len() - failed.len(),
+            urls.len()
+        );
+    }
+    if failed.is_empty() {
+        return Ok(());
+    }
+    eprintln!("Failed:");
+    for url in &failed {
+        eprintln!("  {}", url);
+    }
+    Err(anyhow!("{} of {} clones failed", failed.len(), urls.len()))
+}
+
+/// Clone a single URL
+///
+/// In a `batch` (--from-file) an existing target is never prompted for and no
+/// progress bar is drawn, since several clones may run at once.
+fn clone_url(args: &CloneArgs, url: &str, batch: bool, verbosity: Verbosity) -> Result<()> {
+    // Informational messages, kept off stdout when only the path should be printed
+    let report = |message: String| {
+        if verbosity.is_quiet() {
+            return;
+        }
+        if args.print_path {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
+
+    if let Some(template) = &args.template {
+        if !template.is_dir() {
+            return Err(anyhow!(
+                "Template directory does not exist: {}",
+                template.display()
+            ));
+        }
+    }
+
+    let options = CloneOptions::resolve(args, &Config::load()?)?;
+    let repo_root = get_repo_root(args.root.as_deref())?;
     let url = convert_url_if_needed(url);
     let info = parse_repo_url(&url)?;
 
+    if verbosity.is_verbose() {
+        eprintln!("Repository root: {}", repo_root.display());
+        eprintln!("Resolved URL: {}", url);
+        eprintln!("Parsed: {:?}", info);
+        eprintln!("Clone options: {:?}", options);
+    }
+
+    let repo_name = match &args.name {
+        Some(name) => {
+            if !is_valid_dir_name(name) {
+                return Err(anyhow!("Invalid directory name for --as: {}", name));
+            }
+            name.as_str()
+        }
+        None => info.repo.as_str(),
+    };
+
     // Build target path: <root>/<domain>/<user>/<repo>
     let target_path = repo_root
         .join(&info.domain)
         .join(&info.user)
-        .join(&info.repo);
+        .join(repo_name);
+
+    // With --on-exists replace, the clone goes to a staging directory next to
+    // the existing one, which is only swapped out after the clone succeeds
+    let mut replaces = None;
+    let target_path = if target_path.exists() {
+        match handle_existing(args, &target_path, batch, verbosity)? {
+            Existing::Keep => return Ok(()),
+            Existing::CloneTo(path) => path,
+            Existing::Replace => {
+                let staging = staging_path(&target_path);
+                replaces = Some(target_path);
+                staging
+            }
+        }
+    } else {
+        target_path
+    };
 
     // Create parent directories
     if let Some(parent) = target_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
-    println!("Cloning {} to {}...", url, target_path.display());
+    report(format!(
+        "Cloning {} to {}...",
+        url,
+        replaces.as_ref().unwrap_or(&target_path).display()
+    ));
+
+    // Clone the repository, retrying transient network failures
+    let existed = target_path.exists();
+    let max_attempts = args.retries + 1;
+    let mut attempt = 1;
+    loop {
+        let progress = if batch {
+            None
+        } else {
+            clone_progress(verbosity)
+        };
+        let result =
+            build_repo_builder(args, &options, progress.as_ref()).clone(&url, &target_path);
+        if let Some(bar) = &progress {
+            bar.finish_and_clear();
+        }
+        match result {
+            Ok(_) => break,
+            Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                eprintln!(
+                    "Clone attempt {}/{} failed: {}. Retrying...",
+                    attempt,
+                    max_attempts,
+                    e.message()
+                );
+                // Remove the partial clone before retrying
+                if !existed && target_path.exists() {
+                    fs::remove_dir_all(&target_path)?;
+                }
+                attempt += 1;
+            }
+            Err(e) => {
+                if attempt > 1 {
+                    eprintln!("Clone failed after {} attempts", attempt);
+                }
+                // Leave the existing clone untouched
+                if replaces.is_some() && target_path.exists() {
+                    let _ = fs::remove_dir_all(&target_path);
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    let target_path = match replaces {
+        Some(existing) => {
+            fs::remove_dir_all(&existing)?;
+            fs::rename(&target_path, &existing).map_err(|e| {
+                anyhow!(
+                    "Failed to move the new clone from {} to {}: {}",
+                    target_path.display(),
+                    existing.display(),
+                    e
+                )
+            })?;
+            existing
+        }
+        None => target_path,
+    };
+
+    if args.recurse_submodules {
+        let jobs = args.jobs.unwrap_or(1) as usize;
+        let initialized = AtomicUsize::new(0);
+        let result = update_submodules(&target_path, &target_path, jobs, &initialized, &report);
+        let initialized = initialized.into_inner();
+        report(format!(
+            "Initialized {} submodule{}",
+            initialized,
+            if initialized == 1 { "" } else { "s" }
+        ));
+        result.map_err(|e| anyhow!("Cloned to {}, but {}", target_path.display(), e))?;
+    }
+
+    // Apply template files
+    if let Some(template) = &args.template {
+        let added = apply_template(template, &target_path)?;
+        if added.is_empty() {
+            report("No template files added (all already exist)".to_string());
+        } else {
+            report("Added template files:".to_string());
+            for file in &added {
+                report(format!("  {}", file.display()));
+            }
+        }
+    }
+
+    if verbosity.is_quiet() || args.print_path {
+        // Print only the path so scripts can consume it
+        println!("{}", target_path.display());
+    } else {
+        println!("Successfully cloned to {}", target_path.display());
+    }
+
+    if args.open {
+        open_in_editor(args.editor.as_deref(), &target_path)?;
+    }
+
+    Ok(())
+}
+
+/// What to do about an existing clone target
+enum Existing {
+    /// Nothing left to clone
+    Keep,
+    /// Clone into another directory instead
+    CloneTo(PathBuf),
+    /// Clone again and replace the existing directory
+    Replace,
+}
 
-    // Setup SSH authentication callbacks
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|url, username_from_url, allowed_types| {
-        if allowed_types.contains(git2::CredentialType::SSH_KEY) {
-            Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
-        } else if allowed_types.contains(git2::CredentialType::USERNAME) {
-            Cred::username(username_from_url.unwrap_or("git"))
+/// Decide what to do when the clone target already exists
+fn handle_existing(
+    args: &CloneArgs,
+    target_path: &Path,
+    batch: bool,
+    verbosity: Verbosity,
+) -> Result<Existing> {
+    let action = match args.on_exists {
+        Some(action) => action,
+        None if args.fail_on_exists => OnExists::Fail,
+        None if !batch && std::io::stdin().is_terminal() => prompt_on_exists(target_path)?,
+        None => {
+            // Make the skip visible in pipelines even without --on-exists
+            eprintln!(
+                "Warning: {} already exists; skipping clone (use --on-exists or --fail-on-exists)",
+                target_path.display()
+            );
+            OnExists::Skip
+        }
+    };
+
+    let print_existing = |message: &str| {
+        if verbosity.is_quiet() || args.print_path {
+            println!("{}", target_path.display());
         } else {
-            Err(git2::Error::from_str(&format!(
-                "No supported authentication methods available for URL `{}` with username {:?}; allowed credential types: {:?}",
-                url, username_from_url, allowed_types
-            )))
+            println!("{}: {}", message, target_path.display());
+        }
+    };
+
+    match action {
+        OnExists::Skip => {
+            print_existing("Already exists, skipped");
+            Ok(Existing::Keep)
+        }
+        OnExists::Update => {
+            let status = update_existing(target_path)?;
+            if !verbosity.is_quiet() {
+                eprintln!("{}", status);
+            }
+            print_existing("Updated existing clone");
+            Ok(Existing::Keep)
+        }
+        OnExists::Replace => Ok(Existing::Replace),
+        OnExists::Rename => {
+            let free = next_free_path(target_path);
+            if std::io::stdin().is_terminal() && args.on_exists.is_none() {
+                let default = free
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let existing = target_path.to_path_buf();
+                let name = Text::new("Directory name:")
+                    .with_default(&default)
+                    .with_validator(move |name: &str| {
+                        let name = name.trim();
+                        Ok(if !is_valid_dir_name(name) {
+                            Validation::Invalid(
+                                "Enter a single directory name (no '/', '\\', '.' or '..')".into(),
+                            )
+                        } else if existing.with_file_name(name).exists() {
+                            Validation::Invalid(format!("{} already exists", name).into())
+                        } else {
+                            Validation::Valid
+                        })
+                    })
+                    .prompt()?;
+                Ok(Existing::CloneTo(target_path.with_file_name(name.trim())))
+            } else {
+                Ok(Existing::CloneTo(free))
+            }
+        }
+        OnExists::Fail => Err(anyhow!(
+            "Target already exists, clone skipped: {}",
+            target_path.display()
+        )),
+    }
+}
+
+/// Ask what to do with an existing clone target
+fn prompt_on_exists(target_path: &Path) -> Result<OnExists> {
+    let options = [
+        ("Skip (keep the existing directory)", OnExists::Skip),
+        ("Update (fetch and fast-forward)", OnExists::Update),
+        ("Replace (delete and clone again)", OnExists::Replace),
+        ("Rename (clone under another name)", OnExists::Rename),
+        ("Abort", OnExists::Fail),
+    ];
+    let choice = Select::new(
+        &format!("{} already exists:", target_path.display()),
+        options.iter().map(|(label, _)| *label).collect(),
+    )
+    .raw_prompt()?;
+    let action = options[choice.index].1;
+
+    if let OnExists::Replace = action {
+        let confirmed = Confirm::new(&format!(
+            "Delete {} once the new clone succeeds?",
+            target_path.display()
+        ))
+        .with_default(false)
+        .prompt()?;
+        if !confirmed {
+            return Ok(OnExists::Fail);
+        }
+    }
+    Ok(action)
+}
+
+/// Whether `name` is a single directory name that stays inside its parent
+fn is_valid_dir_name(name: &str) -> bool {
+    !(name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']))
+}
+
+/// Sibling directory a replacing clone is staged in, `<name>.tmp-<pid>`
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.tmp-{}", name, std::process::id()))
+}
+
+/// First `<path>-N` (N >= 2) that doesn't exist yet
+fn next_free_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}-{}", name, n)))
+        .find(|p| !p.exists())
+        .expect("unbounded range always yields a free path")
+}
+
+/// Fetch origin and fast-forward the current branch to its upstream
+///
+/// Returns a short status line. Diverged branches and local changes that
+/// would be overwritten are left alone.
+fn update_existing(path: &Path) -> Result<String> {
+    let repo = Repository::open(path).map_err(|e| {
+        anyhow!(
+            "{} is not a git repository: {}",
+            path.display(),
+            e.message()
+        )
+    })?;
+
+    let mut fetch_opts = FetchOptions::new();
+    fetch_opts.remote_callbacks(auth::remote_callbacks());
+    repo.find_remote("origin")?
+        .fetch::<&str>(&[], Some(&mut fetch_opts), None)?;
+
+    let head = repo.head()?;
+    let Some(branch_name) = head.shorthand().filter(|_| head.is_branch()) else {
+        return Ok("Fetched origin (detached HEAD left as is)".to_string());
+    };
+    let branch = repo.find_branch(branch_name, BranchType::Local)?;
+    let Ok(upstream) = branch.upstream() else {
+        return Ok(format!(
+            "Fetched origin ('{}' has no upstream)",
+            branch_name
+        ));
+    };
+
+    let target = upstream.get().peel_to_commit()?;
+    let annotated = repo.find_annotated_commit(target.id())?;
+    let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+    if analysis.is_up_to_date() {
+        return Ok(format!("'{}' is already up to date", branch_name));
+    }
+    if !analysis.is_fast_forward() {
+        return Ok(format!(
+            "Fetched origin ('{}' has diverged, not fast-forwarded)",
+            branch_name
+        ));
+    }
+
+    // Update the working tree first so local changes abort before the ref moves
+    if !repo.is_bare() {
+        repo.checkout_tree(target.as_object(), Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| anyhow!("Cannot fast-forward '{}': {}", branch_name, e.message()))?;
+    }
+    repo.find_reference(head.name().unwrap_or("HEAD"))?
+        .set_target(target.id(), "git-repos clone: fast-forward")?;
+
+    Ok(format!("Fast-forwarded '{}'", branch_name))
+}
+
+/// Initialize and fetch the submodules of the repository at `path`, recursively
+///
+/// Submodules at every depth share one queue served by `jobs` worker threads.
+/// git2 handles can't be shared between threads, so each worker opens its own.
+/// Registering submodules writes the parent's `.git/config`, so each
+/// repository's submodules are initialized serially by the one worker that
+/// cloned it before they are queued; the parallel updates don't touch it.
+/// A failed submodule is reported and the others still run; the error lists
+/// every failure. `initialized` counts the submodules that were cloned.
+fn update_submodules(
+    root: &Path,
+    path: &Path,
+    jobs: usize,
+    initialized: &AtomicUsize,
+    report: &(dyn Fn(String) + Sync),
+) -> Result<()> {
+    /// Pending `(parent path, submodule name)` pairs and the number of
+    /// workers busy cloning, which may still queue nested submodules
+    struct Queue {
+        pending: VecDeque<(PathBuf, String)>,
+        active: usize,
+    }
+
+    let queue = Mutex::new(Queue {
+        pending: init_submodules(path)?.into(),
+        active: 0,
+    });
+    let changed = Condvar::new();
+    let failed = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| loop {
+                let (parent, name) = {
+                    let mut queue = queue.lock().unwrap();
+                    loop {
+                        if let Some(item) = queue.pending.pop_front() {
+                            queue.active += 1;
+                            break item;
+                        }
+                        if queue.active == 0 {
+                            return;
+                        }
+                        queue = changed.wait(queue).unwrap();
+                    }
+                };
+
+                let result = update_submodule(root, &parent, &name, initialized, report);
+                let mut queue = queue.lock().unwrap();
+                match result {
+                    Ok(nested) => queue.pending.extend(nested),
+                    Err(e) => {
+                        eprintln!("Submodule '{}' failed: {}", name, e);
+                        failed.lock().unwrap().push(name);
+                    }
+                }
+                queue.active -= 1;
+                changed.notify_all();
+            });
         }
     });
 
-    // Setup fetch options with callbacks
+    let failed = failed.into_inner().unwrap();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} submodule{} failed: {}",
+            failed.len(),
+            if failed.len() == 1 { "" } else { "s" },
+            failed.join(", ")
+        ))
+    }
+}
+
+/// Register the submodules of the repository at `path` in its config
+///
+/// Returns `(path, name)` pairs ready to be cloned.
+fn init_submodules(path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let repo = Repository::open(path)?;
+    let mut pending = Vec::new();
+    for mut submodule in repo.submodules()? {
+        submodule.init(false)?;
+        if let Some(name) = submodule.name() {
+            pending.push((path.to_path_buf(), name.to_string()));
+        }
+    }
+    Ok(pending)
+}
+
+/// Clone one already initialized submodule and initialize its own submodules
+///
+/// Returns the nested submodules to clone next.
+fn update_submodule(
+    root: &Path,
+    path: &Path,
+    name: &str,
+    initialized: &AtomicUsize,
+    report: &(dyn Fn(String) + Sync),
+) -> Result<Vec<(PathBuf, String)>> {
+    let repo = Repository::open(path)?;
+    let mut submodule = repo.find_submodule(name)?;
+    let submodule_path = path.join(submodule.path());
+    let display_path = submodule_path.strip_prefix(root).unwrap_or(&submodule_path);
+
+    let mut fetch_opts = FetchOptions::new();
+    fetch_opts.remote_callbacks(auth::remote_callbacks());
+    let mut update_opts = SubmoduleUpdateOptions::new();
+    update_opts.fetch(fetch_opts);
+    submodule.update(false, Some(&mut update_opts))?;
+    initialized.fetch_add(1, Ordering::Relaxed);
+    report(format!(
+        "Cloned submodule '{}' ({})",
+        name,
+        display_path.display()
+    ));
+
+    init_submodules(&submodule_path)
+}
+
+/// Launch the editor in the cloned directory and wait for it to exit
+fn open_in_editor(editor: Option<&str>, path: &Path) -> Result<()> {
+    let editor = editor
+        .map(|e| e.to_string())
+        .or_else(|| std::env::var("VISUAL").ok().filter(|e| !e.is_empty()))
+        .or_else(|| std::env::var("EDITOR").ok().filter(|e| !e.is_empty()))
+        .ok_or_else(|| anyhow!("No editor configured. Use --editor or set $VISUAL/$EDITOR"))?;
+
+    // Allow editor commands with arguments, e.g. "code --new-window"
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow!("Editor command is empty"))?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .current_dir(path)
+        .status()
+        .map_err(|e| anyhow!("Failed to launch editor '{}': {}", editor, e))?;
+
+    if !status.success() {
+        return Err(anyhow!("Editor '{}' exited with {}", editor, status));
+    }
+    Ok(())
+}
+
+/// Progress bar for a clone, or `None` when it shouldn't be drawn
+///
+/// The bar is drawn on stderr, so it is only shown when stderr is a terminal;
+/// this keeps it out of logs and CI output. `--quiet` also disables it.
+fn clone_progress(verbosity: Verbosity) -> Option<ProgressBar> {
+    if verbosity.is_quiet() || !std::io::stderr().is_terminal() {
+        return None;
+    }
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{msg:32} [{bar:30}] {pos}/{len}")
+            .expect("valid progress template")
+            .progress_chars("=> "),
+    );
+    Some(bar)
+}
+
+/// Build the clone builder with authentication callbacks and clone options
+///
+/// With a progress bar, fetch progress (objects, then deltas) and checkout
+/// progress are reported on it.
+fn build_repo_builder(
+    args: &CloneArgs,
+    options: &CloneOptions,
+    progress: Option<&ProgressBar>,
+) -> RepoBuilder<'static> {
+    // Setup fetch options with the suite's authentication callbacks
+    let mut callbacks = auth::remote_callbacks();
+    if let Some(bar) = progress {
+        let bar = bar.clone();
+        callbacks.transfer_progress(move |stats| {
+            if stats.received_objects() < stats.total_objects() {
+                bar.set_message(format!(
+                    "Receiving objects ({})",
+                    HumanBytes(stats.received_bytes() as u64)
+                ));
+                bar.set_length(stats.total_objects() as u64);
+                bar.set_position(stats.received_objects() as u64);
+            } else {
+                bar.set_message("Resolving deltas");
+                bar.set_length(stats.total_deltas() as u64);
+                bar.set_position(stats.indexed_deltas() as u64);
+            }
+            true
+        });
+    }
     let mut fetch_opts = FetchOptions::new();
     fetch_opts.remote_callbacks(callbacks);
 
-    if shallow {
-        fetch_opts.depth(1);
+    if let Some(depth) = options.depth {
+        fetch_opts.depth(depth);
     }
 
     // Setup clone options
     let mut builder = RepoBuilder::new();
     builder.fetch_options(fetch_opts);
 
-    if bare {
+    if let Some(bar) = progress {
+        let bar = bar.clone();
+        let mut checkout = CheckoutBuilder::new();
+        checkout.progress(move |_, current, total| {
+            bar.set_message("Checking out files");
+            bar.set_length(total as u64);
+            bar.set_position(current as u64);
+        });
+        builder.with_checkout(checkout);
+    }
+
+    if args.bare {
         builder.bare(true);
     }
 
-    if let Some(branch_name) = branch {
+    if let Some(branch_name) = &args.branch {
         builder.branch(branch_name);
+
+        // Restrict the fetch refspec to the requested branch
+        if options.single_branch {
+            let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch_name);
+            builder
+                .remote_create(move |repo, name, url| repo.remote_with_fetch(name, url, &refspec));
+        }
     }
 
-    // Clone the repository
-    builder.clone(&url, &target_path)?;
+    builder
+}
 
-    println!("Successfully cloned to {}", target_path.display());
+/// Whether a clone error is a transient network failure worth retrying
+///
+/// Authentication failures, missing repositories and certificate errors are
+/// fatal and never retried.
+fn is_retryable(e: &git2::Error) -> bool {
+    use git2::{ErrorClass, ErrorCode};
 
-    Ok(())
+    if matches!(
+        e.code(),
+        ErrorCode::Auth | ErrorCode::NotFound | ErrorCode::Certificate | ErrorCode::Exists
+    ) {
+        return false;
+    }
+
+    // HTTP client errors (e.g. 401/403/404) are not transient
+    let message = e.message().to_lowercase();
+    if message.contains("not found")
+        || ["401", "403", "404"]
+            .iter()
+            .any(|code| message.contains(code))
+    {
+        return false;
+    }
+
+    matches!(
+        e.class(),
+        ErrorClass::Net | ErrorClass::Http | ErrorClass::Ssh | ErrorClass::Os
+    )
+}
+
+/// Copy files from the template directory into the target, skipping files that
+/// already exist. Returns the added paths relative to the target.
+fn apply_template(template: &Path, target: &Path) -> Result<Vec<PathBuf>> {
+    fn copy_dir(src: &Path, dst: &Path, base: &Path, added: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            let src_path = entry.path();
+            let dst_path = dst.join(entry.file_name());
+
+            if entry.file_type()?.is_dir() {
+                // Never touch the clone's git directory
+                if entry.file_name() == ".git" {
+                    continue;
+                }
+                if !dst_path.exists() {
+                    fs::create_dir_all(&dst_path)?;
+                    added.push(
+                        dst_path
+                            .strip_prefix(base)
+                            .unwrap_or(&dst_path)
+                            .to_path_buf(),
+                    );
+                }
+                copy_dir(&src_path, &dst_path, base, added)?;
+            } else if !dst_path.exists() {
+                fs::copy(&src_path, &dst_path)?;
+                added.push(
+                    dst_path
+                        .strip_prefix(base)
+                        .unwrap_or(&dst_path)
+                        .to_path_buf(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    let mut added = Vec::new();
+    copy_dir(template, target, target, &mut added)?;
+    Ok(added)
 }
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use clap::Args;
+use git2::Repository;
+use inquire::Confirm;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use crate::ls::{repo_preview, select_repo};
+use crate::utils::{get_repo_root, is_bare_repo, repo_path_under_root, Verbosity};
+
+#[derive(Args)]
+pub struct DeleteArgs {
+    /// Repository path relative to the root (e.g. github.com/user/repo); selected interactively if omitted
+    repo: Option<String>,
+
+    /// Also allow deleting bare repositories, which are usually intentional mirrors
+    #[arg(long)]
+    include_bare: bool,
+
+    /// Delete without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Use this repository root instead of the configured one
+    #[arg(long, value_name = "PATH")]
+    root: Option<String>,
+
+    /// Command whose first output line is shown for the highlighted repository; `{}` is
+    /// replaced by the repository path (default: age and subject of the last commit)
+    #[arg(long, value_name = "CMD")]
+    preview_command: Option<String>,
+}
+
+pub fn delete_repo(args: &DeleteArgs, verbosity: Verbosity) -> Result<()> {
+    let repo_root = get_repo_root(args.root.as_deref())?;
+
+    let relative_path = match &args.repo {
+        Some(repo) => repo.clone(),
+        None => {
+            let preview = |path: &Path| repo_preview(args.preview_command.as_deref(), path);
+            select_repo(
+                &repo_root,
+                "Select a repository to delete:",
+                None,
+                Some(&preview),
+            )?
+        }
+    };
+
+    let repo_path = repo_path_under_root(&repo_root, &relative_path)?;
+    if is_bare_repo(&repo_path) {
+        if !args.include_bare {
+            return Err(anyhow!(
+                "Skipped bare repository {} (bare clones are often mirrors); use --include-bare to delete it",
+                relative_path
+            ));
+        }
+    } else if !repo_path.join(".git").exists() {
+        return Err(anyhow!("Not a managed repository: {}", repo_path.display()));
+    }
+
+    if let Ok(repo) = Repository::open(&repo_path) {
+        let dirty = !repo.is_bare()
+            && repo
+                .statuses(None)
+                .map(|statuses| !statuses.is_empty())
+                .unwrap_or(false);
+        if dirty {
+            eprintln!("Warning: {} has uncommitted changes", relative_path);
+        }
+    }
+
+    if !args.yes {
+        if !std::io::stdin().is_terminal() {
+            return Err(anyhow!(
+                "Refusing to delete {} without confirmation; use --yes",
+                relative_path
+            ));
+        }
+        let confirmed = Confirm::new(&format!("Delete {}?", repo_path.display()))
+            .with_default(false)
+            .prompt()?;
+        if !confirmed {
+            println!("Not deleted");
+            return Ok(());
+        }
+    }
+
+    fs::remove_dir_all(&repo_path)?;
+    remove_empty_parents(&repo_path, &repo_root);
+
+    if !verbosity.is_quiet() {
+        println!("Deleted {}", repo_path.display());
+    }
+    Ok(())
+}
+
+/// Remove the `<domain>/<user>` directories left empty by a deletion
+fn remove_empty_parents(path: &Path, root: &Path) {
+    for dir in path.ancestors().skip(1) {
+        // remove_dir only succeeds on empty directories
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
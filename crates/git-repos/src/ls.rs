@@ -1,9 +1,68 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use clap::Args;
 use git2::Repository;
+use git_utils_core::term;
+use glob::Pattern;
+use inquire::Select;
 use serde::Serialize;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::utils::get_repo_root;
+use crate::cd::quote_posix;
+use crate::picker;
+use crate::utils::{get_repo_root, is_bare_repo, JsonArrayWriter, Verbosity};
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Show detailed information
+    #[arg(short, long)]
+    long: bool,
+
+    /// Show absolute paths
+    #[arg(short, long)]
+    absolute: bool,
+
+    /// Show only dirty repositories
+    #[arg(long)]
+    dirty: bool,
+
+    /// Show only repositories with a working-tree file modified within this duration (e.g. 2d, 12h)
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    modified_within: Option<Duration>,
+
+    /// Show only repositories whose current branch matches this name or glob pattern
+    #[arg(long, value_name = "NAME")]
+    branch: Option<String>,
+
+    /// Never open repositories to read branch/status (fast path; leaves them blank with --long)
+    #[arg(long, conflicts_with_all = ["dirty", "group_dirty"])]
+    no_status: bool,
+
+    /// List dirty repositories first, then clean ones
+    #[arg(long)]
+    group_dirty: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Pretty-print JSON output (default when stdout is a terminal)
+    #[arg(long, requires = "json", conflicts_with = "compact")]
+    pretty: bool,
+
+    /// Compact JSON output (default when stdout is piped)
+    #[arg(long, requires = "json")]
+    compact: bool,
+
+    /// Use this repository root instead of the configured one
+    #[arg(long, value_name = "PATH")]
+    root: Option<String>,
+}
 
 #[derive(Serialize)]
 struct RepoEntry {
@@ -14,25 +73,62 @@ struct RepoEntry {
     branch: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     status: Option<String>,
+    #[serde(skip)]
+    is_dirty: bool,
 }
 
-pub fn list_repos(long: bool, absolute: bool, dirty: bool, json: bool) -> Result<()> {
-    let repo_root = get_repo_root()?;
+pub fn list_repos(args: &LsArgs, verbosity: Verbosity) -> Result<()> {
+    let repo_root = get_repo_root(args.root.as_deref())?;
 
-    if !repo_root.exists() {
-        println!("Repository root does not exist: {}", repo_root.display());
-        return Ok(());
+    if verbosity.is_verbose() {
+        eprintln!("Repository root: {}", repo_root.display());
     }
 
-    let repos = find_git_repos(&repo_root)?;
+    // Read the root with a timeout so an unreachable mount fails fast
+    let root_listing = match read_dir_with_timeout(&repo_root) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            if !verbosity.is_quiet() {
+                println!("Repository root does not exist: {}", repo_root.display());
+            }
+            return Ok(());
+        }
+        Err(e) => {
+            return Err(anyhow!(
+                "Repository root is unreachable: {} ({})",
+                repo_root.display(),
+                e
+            ));
+        }
+    };
+
+    let repos = find_git_repos(&repo_root, root_listing);
+
+    if verbosity.is_verbose() {
+        eprintln!("Found {} repositories", repos.len());
+    }
 
     if repos.is_empty() {
-        println!("No repositories found");
+        if !verbosity.is_quiet() {
+            println!("No repositories found");
+        }
         return Ok(());
     }
 
     let mut entries: Vec<RepoEntry> = Vec::new();
 
+    // JSON output is streamed as entries are computed
+    let mut json_writer = if args.json {
+        let pretty = term::use_pretty_json(args.pretty, args.compact);
+        Some(JsonArrayWriter::new(io::stdout().lock(), pretty)?)
+    } else {
+        None
+    };
+
+    let branch_pattern = args.branch.as_deref().map(Pattern::new).transpose()?;
+    let show_details = (args.long || args.json) && !args.no_status;
+    let needs_status = args.dirty || args.group_dirty || show_details;
+
     for repo_path in repos {
         let relative_path = repo_path
             .strip_prefix(&repo_root)
@@ -40,12 +136,40 @@ pub fn list_repos(long: bool, absolute: bool, dirty: bool, json: bool) -> Result
             .to_string_lossy()
             .to_string();
 
+        // Repositories are only opened when something needs them, so the plain
+        // listing (and --no-status) stays a pure directory walk
+        let repo = if needs_status || args.modified_within.is_some() || branch_pattern.is_some() {
+            Repository::open(&repo_path).ok()
+        } else {
+            None
+        };
+        let is_dirty = needs_status && repo.as_ref().is_some_and(|r| !is_repo_clean(r));
+
         // Check if dirty filter is enabled
-        if dirty {
-            if let Ok(repo) = Repository::open(&repo_path) {
-                if is_repo_clean(&repo) {
-                    continue;
-                }
+        if args.dirty && repo.is_some() && !is_dirty {
+            continue;
+        }
+
+        // Check if branch filter is enabled
+        let current_branch = repo.as_ref().and_then(get_current_branch);
+        if let Some(pattern) = &branch_pattern {
+            if !current_branch
+                .as_deref()
+                .is_some_and(|b| pattern.matches(b))
+            {
+                continue;
+            }
+        }
+
+        // Check if modified-within filter is enabled
+        if let Some(window) = args.modified_within {
+            let cutoff = SystemTime::now().checked_sub(window).unwrap_or(UNIX_EPOCH);
+            let recent = repo
+                .as_ref()
+                .and_then(latest_mtime)
+                .is_some_and(|mtime| mtime >= cutoff);
+            if !recent {
+                continue;
             }
         }
 
@@ -54,106 +178,288 @@ pub fn list_repos(long: bool, absolute: bool, dirty: bool, json: bool) -> Result
             absolute_path: None,
             branch: None,
             status: None,
+            is_dirty,
         };
 
-        if absolute {
+        if args.absolute {
             entry.absolute_path = Some(repo_path.to_string_lossy().to_string());
         }
 
-        if long || json {
-            if let Ok(repo) = Repository::open(&repo_path) {
-                entry.branch = get_current_branch(&repo);
-                entry.status = Some(get_repo_status(&repo));
-            }
+        if let Some(repo) = repo.as_ref().filter(|_| show_details) {
+            entry.branch = current_branch;
+            entry.status = Some(if repo.is_bare() {
+                "[bare]".to_string()
+            } else {
+                get_repo_status(is_dirty)
+            });
         }
 
-        entries.push(entry);
+        match &mut json_writer {
+            Some(writer) => writer.write(&entry)?,
+            None => entries.push(entry),
+        }
     }
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&entries)?);
-    } else {
-        for entry in entries {
-            if long {
-                println!(
-                    "{:<50} {:<20} {}",
-                    if absolute {
-                        entry.absolute_path.as_ref().unwrap()
-                    } else {
-                        &entry.path
-                    },
-                    entry.branch.as_deref().unwrap_or(""),
-                    entry.status.as_deref().unwrap_or("")
-                );
-            } else {
-                println!(
-                    "{}",
-                    if absolute {
-                        entry.absolute_path.as_ref().unwrap()
-                    } else {
-                        &entry.path
-                    }
-                );
+    if let Some(writer) = json_writer {
+        writer.finish()?;
+    } else if args.group_dirty {
+        let (dirty, clean): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.is_dirty);
+        if !dirty.is_empty() {
+            println!("# Dirty repositories");
+            for entry in &dirty {
+                print_entry(entry, args);
             }
         }
+        if !clean.is_empty() {
+            if !dirty.is_empty() {
+                println!();
+            }
+            println!("# Clean repositories");
+            for entry in &clean {
+                print_entry(entry, args);
+            }
+        }
+    } else {
+        for entry in &entries {
+            print_entry(entry, args);
+        }
     }
 
     Ok(())
 }
 
-fn find_git_repos(root: &PathBuf) -> Result<Vec<PathBuf>> {
+fn print_entry(entry: &RepoEntry, args: &LsArgs) {
+    let path = if args.absolute {
+        entry.absolute_path.as_ref().unwrap()
+    } else {
+        &entry.path
+    };
+
+    if args.long {
+        println!(
+            "{:<50} {:<20} {}",
+            path,
+            entry.branch.as_deref().unwrap_or(""),
+            entry.status.as_deref().unwrap_or("")
+        );
+    } else {
+        println!("{}", path);
+    }
+}
+
+/// Timeout for reading a single directory during discovery, so an
+/// unreachable network mount can't hang the whole listing
+const READ_DIR_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct DirListing {
+    is_repo: bool,
+    subdirs: Vec<PathBuf>,
+}
+
+/// Read a directory on a helper thread, giving up after `READ_DIR_TIMEOUT`
+fn read_dir_with_timeout(dir: &Path) -> io::Result<DirListing> {
+    let (tx, rx) = mpsc::channel();
+    let dir = dir.to_path_buf();
+
+    thread::spawn(move || {
+        let result = (|| {
+            let mut listing = DirListing {
+                is_repo: false,
+                subdirs: Vec::new(),
+            };
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                if entry.file_name() == ".git" {
+                    listing.is_repo = true;
+                } else {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        listing.subdirs.push(path);
+                    }
+                }
+            }
+            // Bare repositories (e.g. mirrors) have no .git directory
+            listing.is_repo = listing.is_repo || is_bare_repo(&dir);
+            Ok(listing)
+        })();
+        let _ = tx.send(result);
+    });
+
+    rx.recv_timeout(READ_DIR_TIMEOUT).unwrap_or_else(|_| {
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("timed out after {}s", READ_DIR_TIMEOUT.as_secs()),
+        ))
+    })
+}
+
+/// Discover all repositories under the root, failing if the root can't be read
+pub fn discover_repos(repo_root: &Path) -> Result<Vec<PathBuf>> {
+    let root_listing = read_dir_with_timeout(repo_root).map_err(|e| {
+        anyhow!(
+            "Repository root is unreachable: {} ({})",
+            repo_root.display(),
+            e
+        )
+    })?;
+    Ok(find_git_repos(repo_root, root_listing))
+}
+
+/// How long a `--preview-command` may run before the picker gives up on it
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Produces a one-line summary of a repository for the picker
+pub type RepoPreview<'a> = dyn Fn(&Path) -> Option<String> + 'a;
+
+/// Interactively select a repository, optionally narrowed to paths containing `filter`
+///
+/// When `preview` is given, its output for the highlighted repository (absolute
+/// path) is shown below the list; filtering only ever matches the path. Returns
+/// the path relative to the root. A single match is returned without prompting.
+pub fn select_repo(
+    repo_root: &Path,
+    message: &str,
+    filter: Option<&str>,
+    preview: Option<&RepoPreview<'_>>,
+) -> Result<String> {
+    let repos: Vec<String> = discover_repos(repo_root)?
+        .iter()
+        .filter_map(|p| p.strip_prefix(repo_root).ok())
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| filter.is_none_or(|f| p.contains(f)))
+        .collect();
+
+    match repos.len() {
+        0 => return Err(anyhow!("No repositories found")),
+        1 => return Ok(repos.into_iter().next().unwrap()),
+        _ => {}
+    }
+
+    let index = match preview {
+        Some(preview) => picker::select(message, &repos, &|i| preview(&repo_root.join(&repos[i])))?,
+        None => Select::new(message, repos.clone()).raw_prompt()?.index,
+    };
+    Ok(repos[index].clone())
+}
+
+/// Picker preview for a repository: the first output line of `command`, or
+/// the age and subject of the last commit when no command is configured
+pub fn repo_preview(command: Option<&str>, path: &Path) -> Option<String> {
+    match command {
+        Some(command) => run_preview_command(command, path),
+        None => last_commit_summary(path),
+    }
+}
+
+/// Run the preview command through `sh -c` with `{}` replaced by the quoted path
+///
+/// A command still running after [`PREVIEW_TIMEOUT`] is killed, so a slow
+/// preview can't hang the picker.
+fn run_preview_command(command: &str, path: &Path) -> Option<String> {
+    let command = command.replace("{}", &quote_posix(&path.to_string_lossy()));
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // Read on a separate thread so the wait below can time out
+    let mut stdout = child.stdout.take()?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = tx.send(output);
+    });
+
+    let output = rx.recv_timeout(PREVIEW_TIMEOUT);
+    if output.is_err() {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+    match output {
+        Ok(output) => String::from_utf8_lossy(&output)
+            .lines()
+            .next()
+            .map(|line| line.trim().to_string()),
+        Err(_) => Some(format!(
+            "(preview timed out after {}s)",
+            PREVIEW_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Age and subject of the last commit, like `git log -1 --format="%cr %s"`
+fn last_commit_summary(path: &Path) -> Option<String> {
+    let repo = Repository::open(path).ok()?;
+    let commit = repo.head().ok()?.peel_to_commit().ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    let age = (now - commit.time().seconds()).max(0);
+    Some(format!(
+        "{} - {}",
+        term::format_age(age),
+        commit.summary().unwrap_or("")
+    ))
+}
+
+fn find_git_repos(root: &Path, root_listing: DirListing) -> Vec<PathBuf> {
     // Maximum depth for repository discovery
-    // For <root>/<domain>/<user>/<repo> layout, we need depth of 3
-    const MAX_DEPTH: usize = 3;
+    // <root>/<domain>/<user>/<repo> needs a depth of 3; the extra levels cover
+    // nested namespaces such as GitLab subgroups (<domain>/<group>/<sub>/<repo>)
+    const MAX_DEPTH: usize = 6;
 
     let mut repos = Vec::new();
 
     fn visit_dirs(
-        dir: &PathBuf,
+        dir: &Path,
+        listing: DirListing,
         repos: &mut Vec<PathBuf>,
         depth: usize,
         max_depth: usize,
-    ) -> Result<()> {
-        if !dir.is_dir() {
-            return Ok(());
-        }
-
+    ) {
         // Check if this is a git repository
-        if dir.join(".git").exists() {
-            repos.push(dir.clone());
-            return Ok(()); // Don't recurse into subdirectories of a git repo
+        if listing.is_repo {
+            repos.push(dir.to_path_buf());
+            return; // Don't recurse into subdirectories of a git repo
         }
 
         // Stop recursion if we've reached max depth
         if depth >= max_depth {
-            return Ok(());
+            return;
         }
 
-        // Recurse into subdirectories
-        for entry in std::fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                visit_dirs(&path, repos, depth + 1, max_depth)?;
+        // Recurse into subdirectories, skipping unreadable ones
+        for path in listing.subdirs {
+            match read_dir_with_timeout(&path) {
+                Ok(sub_listing) => visit_dirs(&path, sub_listing, repos, depth + 1, max_depth),
+                Err(e) => eprintln!("Warning: skipping {}: {}", path.display(), e),
             }
         }
-
-        Ok(())
     }
 
-    visit_dirs(root, &mut repos, 0, MAX_DEPTH)?;
-    Ok(repos)
+    visit_dirs(root, root_listing, &mut repos, 0, MAX_DEPTH);
+    repos
 }
 
 fn get_current_branch(repo: &Repository) -> Option<String> {
-    repo.head().ok()?.shorthand().map(|s| s.to_string())
+    if let Ok(head) = repo.head() {
+        return head.shorthand().map(|s| s.to_string());
+    }
+    // Unborn branch (no commits yet): read where HEAD points
+    let head = repo.find_reference("HEAD").ok()?;
+    head.symbolic_target()?
+        .strip_prefix("refs/heads/")
+        .map(|s| s.to_string())
 }
 
-fn get_repo_status(repo: &Repository) -> String {
-    if is_repo_clean(repo) {
-        "[clean]".to_string()
-    } else {
+fn get_repo_status(is_dirty: bool) -> String {
+    if is_dirty {
         "[dirty]".to_string()
+    } else {
+        "[clean]".to_string()
     }
 }
 
@@ -164,3 +470,50 @@ fn is_repo_clean(repo: &Repository) -> bool {
         true
     }
 }
+
+/// Maximum number of working-tree entries inspected per repository by `latest_mtime`
+const MTIME_WALK_LIMIT: usize = 10_000;
+
+/// Most recent modification time of any non-ignored file in the working tree
+///
+/// Both tracked and untracked files count. The walk skips `.git` and ignored
+/// paths and stops after `MTIME_WALK_LIMIT` entries so huge trees stay fast.
+fn latest_mtime(repo: &Repository) -> Option<SystemTime> {
+    let workdir = repo.workdir()?;
+    let mut latest: Option<SystemTime> = None;
+    let mut stack = vec![workdir.to_path_buf()];
+    let mut visited = 0;
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            visited += 1;
+            if visited > MTIME_WALK_LIMIT {
+                return latest;
+            }
+
+            let path = entry.path();
+            if entry.file_name() == ".git" {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(workdir) {
+                if repo.is_path_ignored(relative).unwrap_or(false) {
+                    continue;
+                }
+            }
+
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                stack.push(path);
+            } else if let Ok(mtime) = entry.metadata().and_then(|m| m.modified()) {
+                latest = latest.max(Some(mtime));
+            }
+        }
+    }
+
+    latest
+}
@@ -1,17 +1,42 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use git_utils_core::term::{self, ColorChoice};
+use inquire::ui::RenderConfig;
+use std::process::ExitCode;
 
+mod adopt;
+mod archive;
+mod cd;
 mod clone;
+mod delete;
 mod ls;
+mod picker;
 mod utils;
 
-use clone::clone_repo;
-use ls::list_repos;
+use adopt::{adopt_repo, AdoptArgs};
+use archive::{archive_repo, ArchiveArgs};
+use cd::{cd_repo, CdArgs};
+use clone::{clone_repo, CloneArgs};
+use delete::{delete_repo, DeleteArgs};
+use ls::{list_repos, LsArgs};
+use utils::Verbosity;
 
 #[derive(Parser)]
-#[command(name = "git-repos")]
+#[command(name = "git-repos", version)]
 #[command(about = "Manage git repositories", long_about = None)]
 struct Cli {
+    /// Suppress informational output (errors are still shown)
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Show debug details
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// When to use colors: auto, always, never
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -19,62 +44,54 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Clone a repository to the managed location
-    Clone {
-        /// Repository URL
-        url: String,
+    Clone(CloneArgs),
 
-        /// Shallow clone with --depth=1
-        #[arg(long)]
-        shallow: bool,
+    /// List all managed repositories
+    Ls(LsArgs),
 
-        /// Clone as bare repository
-        #[arg(long)]
-        bare: bool,
+    /// Create a tar/zip archive of a managed repository
+    Archive(ArchiveArgs),
 
-        /// Checkout specific branch
-        #[arg(short, long)]
-        branch: Option<String>,
-    },
+    /// Move an existing repository into the managed layout based on its origin URL
+    Adopt(AdoptArgs),
 
-    /// List all managed repositories
-    Ls {
-        /// Show detailed information
-        #[arg(short, long)]
-        long: bool,
-
-        /// Show absolute paths
-        #[arg(short, long)]
-        absolute: bool,
-
-        /// Show only dirty repositories
-        #[arg(long)]
-        dirty: bool,
-
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
+    /// Select a repository and print its path (or a `cd` command with --exec)
+    Cd(CdArgs),
+
+    /// Delete a managed repository (bare repositories only with --include-bare)
+    Delete(DeleteArgs),
+}
+
+fn main() -> ExitCode {
+    term::exit_code(run())
 }
 
-fn main() -> Result<()> {
+fn run() -> Result<()> {
     let cli = Cli::parse();
+    let verbosity = Verbosity::from_flags(cli.quiet, cli.verbose);
+
+    if !term::use_color(cli.color) {
+        inquire::set_global_render_config(RenderConfig::empty());
+    }
 
     match cli.command {
-        Commands::Clone {
-            url,
-            shallow,
-            bare,
-            branch,
-        } => {
-            clone_repo(&url, shallow, bare, branch.as_deref())?;
+        Commands::Clone(args) => {
+            clone_repo(&args, verbosity)?;
+        }
+        Commands::Ls(args) => {
+            list_repos(&args, verbosity)?;
+        }
+        Commands::Archive(args) => {
+            archive_repo(&args, verbosity)?;
+        }
+        Commands::Adopt(args) => {
+            adopt_repo(&args, verbosity)?;
+        }
+        Commands::Cd(args) => {
+            cd_repo(&args, verbosity)?;
         }
-        Commands::Ls {
-            long,
-            absolute,
-            dirty,
-            json,
-        } => {
-            list_repos(long, absolute, dirty, json)?;
+        Commands::Delete(args) => {
+            delete_repo(&args, verbosity)?;
         }
     }
 
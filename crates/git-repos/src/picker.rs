@@ -0,0 +1,229 @@
+//! Fuzzy picker that shows a preview line for the highlighted option
+//!
+//! inquire's `Select` renders every option through `Display` up front, so a
+//! per-option preview would have to be computed for the whole list and would
+//! become part of the filtered text. This picker filters on the options alone
+//! and asks for the preview of the highlighted one only, caching the result.
+
+use crossterm::cursor::{Hide, MoveToColumn, MoveUp, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType};
+use git_utils_core::term;
+use inquire::error::{InquireError, InquireResult};
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+
+/// Number of options shown at once, same as inquire's default
+const PAGE_SIZE: usize = 7;
+
+const HELP_MESSAGE: &str = "[↑↓ to move, enter to select, type to filter]";
+
+/// Let the user pick one of `options`, returning its index
+///
+/// `preview` is called with the index of the highlighted option the first time
+/// it is highlighted. Errors mirror inquire's, so cancelling is reported as
+/// `OperationCanceled` / `OperationInterrupted`.
+pub fn select(
+    message: &str,
+    options: &[String],
+    preview: &dyn Fn(usize) -> Option<String>,
+) -> InquireResult<usize> {
+    if !io::stdin().is_terminal() {
+        return Err(InquireError::NotTTY);
+    }
+
+    let mut picker = Picker {
+        message,
+        options,
+        preview,
+        previews: HashMap::new(),
+        input: String::new(),
+        matches: filter_options(options, ""),
+        cursor: 0,
+        offset: 0,
+        rendered_lines: 0,
+    };
+
+    let mut stderr = io::stderr();
+    terminal::enable_raw_mode()?;
+    queue!(stderr, Hide)?;
+    let result = picker.run(&mut stderr);
+    let _ = queue!(stderr, Show);
+    let _ = stderr.flush();
+    let _ = terminal::disable_raw_mode();
+    result
+}
+
+/// Indices of the options matching `input`, best match first
+///
+/// Uses the suite's fuzzy ranking; an empty input keeps every option in its
+/// original order.
+fn filter_options(options: &[String], input: &str) -> Vec<usize> {
+    let mut matches: Vec<(usize, &str)> = options.iter().map(String::as_str).enumerate().collect();
+    term::rank_by_pattern(&mut matches, input, |&(_, option)| option);
+    matches.into_iter().map(|(i, _)| i).collect()
+}
+
+struct Picker<'a> {
+    message: &'a str,
+    options: &'a [String],
+    preview: &'a dyn Fn(usize) -> Option<String>,
+    previews: HashMap<usize, Option<String>>,
+    input: String,
+    matches: Vec<usize>,
+    /// Position of the highlighted option within `matches`
+    cursor: usize,
+    /// Position of the first visible option within `matches`
+    offset: usize,
+    rendered_lines: u16,
+}
+
+impl Picker<'_> {
+    fn run(&mut self, out: &mut impl Write) -> InquireResult<usize> {
+        loop {
+            self.render(out)?;
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => key,
+                _ => continue,
+            };
+
+            match self.handle_key(key) {
+                Some(Ok(index)) => {
+                    self.clear(out)?;
+                    queue!(
+                        out,
+                        Print(format!("> {} {}\r\n", self.message, self.options[index]))
+                    )?;
+                    return Ok(index);
+                }
+                Some(Err(e)) => {
+                    self.clear(out)?;
+                    return Err(e);
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Apply a key press, returning the outcome once the prompt is answered or cancelled
+    fn handle_key(&mut self, key: KeyEvent) -> Option<InquireResult<usize>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if ctrl => return Some(Err(InquireError::OperationInterrupted)),
+            KeyCode::Esc => return Some(Err(InquireError::OperationCanceled)),
+            KeyCode::Enter => return self.matches.get(self.cursor).map(|&i| Ok(i)),
+            KeyCode::Up | KeyCode::BackTab => self.move_cursor(-1),
+            KeyCode::Char('p') if ctrl => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Tab => self.move_cursor(1),
+            KeyCode::Char('n') if ctrl => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-(PAGE_SIZE as isize)),
+            KeyCode::PageDown => self.move_cursor(PAGE_SIZE as isize),
+            KeyCode::Backspace if self.input.pop().is_some() => self.refilter(),
+            KeyCode::Char(c) if !ctrl => {
+                self.input.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn refilter(&mut self) {
+        self.matches = filter_options(self.options, &self.input);
+        self.cursor = 0;
+        self.offset = 0;
+    }
+
+    /// Move the highlight by `delta`, wrapping around for single steps
+    fn move_cursor(&mut self, delta: isize) {
+        let len = self.matches.len() as isize;
+        if len == 0 {
+            return;
+        }
+        let target = self.cursor as isize + delta;
+        self.cursor = if delta.abs() == 1 {
+            target.rem_euclid(len)
+        } else {
+            target.clamp(0, len - 1)
+        } as usize;
+
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + PAGE_SIZE {
+            self.offset = self.cursor + 1 - PAGE_SIZE;
+        }
+    }
+
+    fn render(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let width = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
+        let fit = |line: String| -> String { line.chars().take(width.saturating_sub(1)).collect() };
+
+        let mut lines = vec![format!("? {} {}", self.message, self.input)];
+        for (pos, &index) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(PAGE_SIZE)
+        {
+            let marker = if pos == self.cursor { ">" } else { " " };
+            lines.push(format!("{} {}", marker, self.options[index]));
+        }
+        match self.matches.get(self.cursor) {
+            Some(&index) => {
+                let preview = self.preview;
+                if let Some(summary) = self.previews.entry(index).or_insert_with(|| preview(index))
+                {
+                    lines.push(format!("  {}", summary));
+                }
+            }
+            None => lines.push("  No matching options".to_string()),
+        }
+        lines.push(HELP_MESSAGE.to_string());
+
+        self.clear(out)?;
+        let frame: Vec<String> = lines.into_iter().map(fit).collect();
+        queue!(out, Print(frame.join("\r\n")))?;
+        out.flush()?;
+        self.rendered_lines = frame.len() as u16;
+        Ok(())
+    }
+
+    /// Erase the previously rendered frame, leaving the cursor where it started
+    fn clear(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.rendered_lines > 1 {
+            queue!(out, MoveUp(self.rendered_lines - 1))?;
+        }
+        queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+        self.rendered_lines = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_options() {
+        let options: Vec<String> = [
+            "github.com/user/tool",
+            "github.com/user/website",
+            "gitlab.com/group/tool",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        assert_eq!(filter_options(&options, ""), vec![0, 1, 2]);
+        assert_eq!(filter_options(&options, "web"), vec![1]);
+
+        let tools = filter_options(&options, "tool");
+        assert_eq!(tools.len(), 2);
+        assert!(tools.contains(&0) && tools.contains(&2));
+        assert!(filter_options(&options, "nomatch").is_empty());
+    }
+}
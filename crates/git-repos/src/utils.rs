@@ -1,18 +1,54 @@
 use anyhow::{anyhow, Result};
-use git2::Config;
-use std::path::PathBuf;
+use git_utils_core::config::Config;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
 use url::Url;
 
-/// Get the repository root from git config or environment variable
-pub fn get_repo_root() -> Result<PathBuf> {
-    // Try git config first
-    if let Ok(config) = Config::open_default() {
-        if let Ok(root) = config.get_string("git-repos.root") {
-            let expanded = shellexpand::tilde(&root);
-            return Ok(PathBuf::from(expanded.as_ref()));
+/// Output verbosity level controlled by the global `-q`/`-v` flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    pub fn from_flags(quiet: bool, verbose: bool) -> Self {
+        if quiet {
+            Verbosity::Quiet
+        } else if verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
         }
     }
 
+    /// Whether informational messages should be printed
+    pub fn is_quiet(&self) -> bool {
+        *self == Verbosity::Quiet
+    }
+
+    /// Whether debug details should be printed
+    pub fn is_verbose(&self) -> bool {
+        *self == Verbosity::Verbose
+    }
+}
+
+/// Get the repository root from the override, configuration or environment variable
+pub fn get_repo_root(root_override: Option<&str>) -> Result<PathBuf> {
+    // A --root flag takes precedence over everything else
+    if let Some(root) = root_override {
+        let expanded = shellexpand::tilde(root);
+        return Ok(PathBuf::from(expanded.as_ref()));
+    }
+
+    // Try configuration first (gitconfig or config.toml)
+    if let Some(root) = Config::load()?.get_string("git-repos.root") {
+        let expanded = shellexpand::tilde(&root);
+        return Ok(PathBuf::from(expanded.as_ref()));
+    }
+
     // Fall back to GIT_REPOS_ROOT environment variable
     if let Ok(root) = std::env::var("GIT_REPOS_ROOT") {
         let expanded = shellexpand::tilde(&root);
@@ -23,18 +59,89 @@ pub fn get_repo_root() -> Result<PathBuf> {
         "Repository root not configured.\n\
          Set either:\n\
          - git config --global git-repos.root <path>\n\
+         - root = \"<path>\" under [git-repos] in ~/.git-utils/config.toml\n\
          - export GIT_REPOS_ROOT=<path> (in your shell rc file)"
     ))
 }
 
-/// Check if SSH is preferred from git config
+/// Whether `path` is a bare repository (`HEAD`, `objects/` and `refs/` at the top level)
+///
+/// The same layout check git itself uses, so it works without opening the repository.
+pub fn is_bare_repo(path: &Path) -> bool {
+    path.join("HEAD").is_file() && path.join("objects").is_dir() && path.join("refs").is_dir()
+}
+
+/// Whether `path` is a repository with a working tree or a bare repository
+pub fn is_repo_dir(path: &Path) -> bool {
+    path.join(".git").exists() || is_bare_repo(path)
+}
+
+/// Resolve a user-supplied repository path (e.g. `github.com/user/repo`) under the root
+///
+/// Only plain path components are accepted, so the result never points
+/// outside the root.
+pub fn repo_path_under_root(repo_root: &Path, relative_path: &str) -> Result<PathBuf> {
+    if Path::new(relative_path)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(anyhow!("Invalid repository path: {}", relative_path));
+    }
+    Ok(repo_root.join(relative_path))
+}
+
+/// Check if SSH is preferred from configuration
 pub fn prefer_ssh() -> bool {
-    if let Ok(config) = Config::open_default() {
-        if let Ok(prefer) = config.get_bool("git-repos.prefer-ssh") {
-            return prefer;
+    Config::load()
+        .ok()
+        .and_then(|config| config.get_bool("git-repos.prefer-ssh"))
+        .unwrap_or(false)
+}
+
+/// Writes a JSON array incrementally, one element at a time
+///
+/// Elements are emitted as soon as they are written, so memory stays bounded
+/// and output starts appearing before the whole list is computed.
+pub struct JsonArrayWriter<W: Write> {
+    writer: W,
+    pretty: bool,
+    count: usize,
+}
+
+impl<W: Write> JsonArrayWriter<W> {
+    pub fn new(mut writer: W, pretty: bool) -> io::Result<Self> {
+        writer.write_all(b"[")?;
+        Ok(Self {
+            writer,
+            pretty,
+            count: 0,
+        })
+    }
+
+    pub fn write<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        if self.count > 0 {
+            self.writer.write_all(b",")?;
+        }
+        if self.pretty {
+            let json = serde_json::to_string_pretty(value)?;
+            for line in json.lines() {
+                write!(self.writer, "\n  {}", line)?;
+            }
+        } else {
+            serde_json::to_writer(&mut self.writer, value)?;
         }
+        self.writer.flush()?;
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.pretty && self.count > 0 {
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.write_all(b"]\n")?;
+        self.writer.flush()
     }
-    false
 }
 
 #[derive(Debug)]
@@ -45,45 +152,71 @@ pub struct RepoInfo {
 }
 
 /// Parse repository URL to extract domain, user, and repo name
+///
+/// Accepts scp-like SSH URLs (`git@host:group/repo.git`) and URLs with a scheme
+/// (`https://`, `ssh://git@host:2222/...`). Ports are dropped from the domain.
 pub fn parse_repo_url(url_str: &str) -> Result<RepoInfo> {
-    // Handle SSH URLs like git@github.com:user/repo.git
-    if url_str.starts_with("git@") {
-        let parts: Vec<&str> = url_str.split(':').collect();
-        if parts.len() != 2 {
+    // Handle scp-like SSH URLs like git@github.com:user/repo.git
+    if !url_str.contains("://") {
+        let (host, path) = url_str
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid SSH URL format"))?;
+        let domain = host.rsplit_once('@').map_or(host, |(_, domain)| domain);
+        if domain.is_empty() || path.contains(':') {
             return Err(anyhow!("Invalid SSH URL format"));
         }
-
-        let domain = parts[0].trim_start_matches("git@");
-        let path = parts[1].trim_end_matches(".git");
-        let path_parts: Vec<&str> = path.split('/').collect();
-
-        if path_parts.len() < 2 {
-            return Err(anyhow!("Invalid repository path"));
-        }
-
-        Ok(RepoInfo {
-            domain: domain.to_string(),
-            user: path_parts[0].to_string(),
-            repo: path_parts[1].to_string(),
-        })
+        repo_info_from_path(domain, path)
     } else {
-        // Handle HTTPS URLs
+        // Handle HTTPS and ssh:// URLs
         let url = Url::parse(url_str)?;
         let domain = url.host_str().ok_or_else(|| anyhow!("No host in URL"))?;
+        repo_info_from_path(domain, url.path())
+    }
+}
 
-        let path = url.path().trim_start_matches('/').trim_end_matches(".git");
-        let parts: Vec<&str> = path.split('/').collect();
+/// Owner directory used for gists whose URL doesn't name the owner
+const UNKNOWN_GIST_OWNER: &str = "_";
 
-        if parts.len() < 2 {
-            return Err(anyhow!("Invalid repository path"));
-        }
+/// Build repository info from a host and a `user/repo(.git)` path
+///
+/// Everything before the last segment is the owner, so nested groups such as
+/// GitLab's `group/subgroup/repo` give the user `group/subgroup`. Gist URLs
+/// may omit the owner (`gist.github.com/<hash>.git`); those are placed under
+/// [`UNKNOWN_GIST_OWNER`] so the layout stays `<domain>/<user>/<repo>`.
+///
+/// Every segment becomes a directory under the repository root, so empty,
+/// `.` and `..` segments and backslashes are rejected to keep clones inside it.
+fn repo_info_from_path(domain: &str, path: &str) -> Result<RepoInfo> {
+    let path = path
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+    let parts: Vec<&str> = path.split('/').collect();
 
-        Ok(RepoInfo {
-            domain: domain.to_string(),
-            user: parts[0].to_string(),
-            repo: parts[1].to_string(),
-        })
+    if !is_safe_segment(domain) || !parts.iter().all(|part| is_safe_segment(part)) {
+        return Err(anyhow!("Invalid repository path"));
     }
+
+    let (user, repo) = match parts.as_slice() {
+        [hash] if is_gist_host(domain) => (UNKNOWN_GIST_OWNER.to_string(), *hash),
+        [owner @ .., repo] if !owner.is_empty() => (owner.join("/"), *repo),
+        _ => return Err(anyhow!("Invalid repository path")),
+    };
+
+    Ok(RepoInfo {
+        domain: domain.to_string(),
+        user,
+        repo: repo.to_string(),
+    })
+}
+
+/// Whether a URL segment can be used as a single directory name
+fn is_safe_segment(segment: &str) -> bool {
+    !segment.is_empty() && segment != "." && segment != ".." && !segment.contains(['/', '\\'])
+}
+
+fn is_gist_host(domain: &str) -> bool {
+    domain.starts_with("gist.")
 }
 
 /// Convert HTTPS URL to SSH if needed
@@ -92,8 +225,11 @@ pub fn convert_url_if_needed(url: &str) -> String {
         return url.to_string();
     }
 
-    // Convert HTTPS to SSH
-    if let Ok(parsed) = Url::parse(url) {
+    // Convert HTTPS to SSH (ssh:// URLs are kept, as they may carry a port)
+    if let Some(parsed) = Url::parse(url)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "https" | "http"))
+    {
         if let Some(host) = parsed.host_str() {
             let path = parsed.path().trim_start_matches('/');
             return format!("git@{}:{}", host, path);
@@ -124,4 +260,108 @@ mod tests {
         assert_eq!(info.user, "poi2");
         assert_eq!(info.repo, "git-utils");
     }
+
+    #[test]
+    fn test_parse_urls_with_port_and_subgroups() {
+        let info =
+            parse_repo_url("ssh://git@gitlab.example.com:2222/group/subgroup/repo.git").unwrap();
+        assert_eq!(info.domain, "gitlab.example.com");
+        assert_eq!(info.user, "group/subgroup");
+        assert_eq!(info.repo, "repo");
+
+        let info = parse_repo_url("https://gitlab.com/group/a/b/repo").unwrap();
+        assert_eq!(info.domain, "gitlab.com");
+        assert_eq!(info.user, "group/a/b");
+        assert_eq!(info.repo, "repo");
+
+        let info = parse_repo_url("gitlab@gitlab.com:group/subgroup/repo.git").unwrap();
+        assert_eq!(info.domain, "gitlab.com");
+        assert_eq!(info.user, "group/subgroup");
+        assert_eq!(info.repo, "repo");
+    }
+
+    #[test]
+    fn test_parse_rejects_path_traversal() {
+        for url in [
+            "git@host:../../../tmp/x.git",
+            "git@host:group/../x.git",
+            "git@..:user/x.git",
+            "https://example.com/group//x",
+            "git@host:group\\..\\x.git",
+        ] {
+            assert!(parse_repo_url(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_parse_gist_urls() {
+        let info = parse_repo_url("https://gist.github.com/poi2/0123abcd.git").unwrap();
+        assert_eq!(info.domain, "gist.github.com");
+        assert_eq!(info.user, "poi2");
+        assert_eq!(info.repo, "0123abcd");
+
+        let info = parse_repo_url("https://gist.github.com/0123abcd.git").unwrap();
+        assert_eq!(info.user, UNKNOWN_GIST_OWNER);
+        assert_eq!(info.repo, "0123abcd");
+
+        let info = parse_repo_url("git@gist.github.com:0123abcd.git").unwrap();
+        assert_eq!(info.domain, "gist.github.com");
+        assert_eq!(info.user, UNKNOWN_GIST_OWNER);
+        assert_eq!(info.repo, "0123abcd");
+
+        // Only gist hosts accept a bare hash
+        assert!(parse_repo_url("https://github.com/0123abcd.git").is_err());
+    }
+
+    #[test]
+    fn test_is_bare_repo() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let bare = dir.path().join("mirror.git");
+        let normal = dir.path().join("repo");
+        git2::Repository::init_bare(&bare).unwrap();
+        git2::Repository::init(&normal).unwrap();
+
+        assert!(is_bare_repo(&bare));
+        assert!(!is_bare_repo(&normal));
+        assert!(is_repo_dir(&bare));
+        assert!(is_repo_dir(&normal));
+        assert!(!is_repo_dir(dir.path()));
+    }
+
+    #[test]
+    fn test_repo_path_under_root() {
+        let root = Path::new("/src");
+        assert_eq!(
+            repo_path_under_root(root, "github.com/user/repo").unwrap(),
+            Path::new("/src/github.com/user/repo")
+        );
+        assert!(repo_path_under_root(root, "../../etc").is_err());
+        assert!(repo_path_under_root(root, "github.com/../../x").is_err());
+        assert!(repo_path_under_root(root, "/etc").is_err());
+        assert!(repo_path_under_root(root, "./github.com/user/repo").is_err());
+    }
+
+    #[test]
+    fn test_json_array_writer() {
+        let mut buf = Vec::new();
+        let mut writer = JsonArrayWriter::new(&mut buf, false).unwrap();
+        writer.write(&1).unwrap();
+        writer.write(&"two").unwrap();
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "[1,\"two\"]\n");
+
+        let mut buf = Vec::new();
+        JsonArrayWriter::new(&mut buf, true)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "[]\n");
+
+        let mut buf = Vec::new();
+        let mut writer = JsonArrayWriter::new(&mut buf, true).unwrap();
+        writer.write(&serde_json::json!({ "path": "a" })).unwrap();
+        writer.finish().unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed, serde_json::json!([{ "path": "a" }]));
+    }
 }
@@ -0,0 +1,214 @@
+use crate::config::Config;
+use git2::{Cred, CredentialType, RemoteCallbacks};
+use inquire::Password;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Private keys tried when the SSH agent can't authenticate, in order
+const SSH_KEY_FILES: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// Held while asking for a passphrase, so parallel clones and fetches take
+/// turns at the terminal instead of drawing prompts over each other
+static PROMPT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Build remote callbacks with the suite's credential handling
+///
+/// SSH remotes use the SSH agent first, then the default key files in
+/// `~/.ssh` (asking for a passphrase when a key needs one and stdin is a
+/// terminal; concurrent callers ask one at a time). HTTPS remotes use git's
+/// credential helpers, then a `GH_TOKEN`/`GITHUB_TOKEN` token, which is only
+/// sent to GitHub hosts (see [`is_github_host`]). Every git2 fetch or clone
+/// should use these callbacks so authentication behaves the same everywhere.
+pub fn remote_callbacks() -> RemoteCallbacks<'static> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut state = AuthState::default();
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        state.next_credential(url, username_from_url, allowed_types)
+    });
+    callbacks
+}
+
+/// Credentials already tried for a connection
+///
+/// libgit2 calls the credential callback again after each rejected attempt,
+/// so each step is only tried once to avoid looping forever.
+#[derive(Default)]
+struct AuthState {
+    ssh_agent_tried: bool,
+    /// Key files tried so far, with whether a passphrase was already asked for
+    ssh_keys_tried: Vec<(PathBuf, bool)>,
+    helper_tried: bool,
+    token_tried: bool,
+}
+
+impl AuthState {
+    fn next_credential(
+        &mut self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed_types: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        let username = username_from_url.unwrap_or("git");
+
+        if allowed_types.contains(CredentialType::SSH_KEY) {
+            if !self.ssh_agent_tried {
+                self.ssh_agent_tried = true;
+                if let Ok(cred) = Cred::ssh_key_from_agent(username) {
+                    return Ok(cred);
+                }
+            }
+            if let Some(cred) = self.next_ssh_key(username) {
+                return Ok(cred);
+            }
+        }
+
+        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if !self.helper_tried {
+                self.helper_tried = true;
+                if let Ok(cred) = git2::Config::open_default()
+                    .and_then(|config| Cred::credential_helper(&config, url, username_from_url))
+                {
+                    return Ok(cred);
+                }
+            }
+            if !self.token_tried {
+                self.token_tried = true;
+                if let Some(token) = url_host(url)
+                    .filter(|host| is_github_host(host))
+                    .and(env_token())
+                {
+                    return Cred::userpass_plaintext("x-access-token", &token);
+                }
+            }
+        }
+
+        if allowed_types.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+
+        Err(git2::Error::from_str(&format!(
+            "No supported authentication methods available for URL `{}` with username {:?}; allowed credential types: {:?}",
+            url, username_from_url, allowed_types
+        )))
+    }
+
+    /// Try each default key without a passphrase, then once with one
+    fn next_ssh_key(&mut self, username: &str) -> Option<Cred> {
+        let home = dirs::home_dir()?;
+        for key in ssh_key_candidates(&home) {
+            let tried = self
+                .ssh_keys_tried
+                .iter_mut()
+                .find(|(path, _)| *path == key);
+            let passphrase = match tried {
+                None => {
+                    self.ssh_keys_tried.push((key.clone(), false));
+                    None
+                }
+                Some((_, asked @ false)) if std::io::stdin().is_terminal() => {
+                    *asked = true;
+                    let _prompt = PROMPT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+                    Some(
+                        Password::new(&format!("Passphrase for {}:", key.display()))
+                            .without_confirmation()
+                            .prompt()
+                            .ok()?,
+                    )
+                }
+                Some(_) => continue,
+            };
+
+            if let Ok(cred) = Cred::ssh_key(username, None, &key, passphrase.as_deref()) {
+                return Some(cred);
+            }
+        }
+        None
+    }
+}
+
+/// Default private key files that exist under `home`
+fn ssh_key_candidates(home: &Path) -> Vec<PathBuf> {
+    SSH_KEY_FILES
+        .iter()
+        .map(|name| home.join(".ssh").join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Host of a remote URL such as `https://user@host:8443/path`, lowercased
+fn url_host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split('/').next()?;
+    let host_port = authority.rsplit('@').next()?;
+    let host = host_port.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Whether a GitHub token may be sent to `host`
+///
+/// Only github.com, the `GH_HOST` host (as used by `gh`) and hosts listed in
+/// `git-utils.github-host` (for GitHub Enterprise) qualify, so a token is never
+/// offered to an unrelated server that asks for a password.
+fn is_github_host(host: &str) -> bool {
+    let configured = Config::load()
+        .map(|config| config.get_list("git-utils.github-host"))
+        .unwrap_or_default();
+    let gh_host = std::env::var("GH_HOST").ok();
+    github_host_matches(host, gh_host.as_deref(), &configured)
+}
+
+fn github_host_matches(host: &str, gh_host: Option<&str>, configured: &[String]) -> bool {
+    host == "github.com"
+        || gh_host.is_some_and(|h| h.eq_ignore_ascii_case(host))
+        || configured.iter().any(|h| h.eq_ignore_ascii_case(host))
+}
+
+/// GitHub token from the environment
+///
+/// Checks `GH_TOKEN` and `GITHUB_TOKEN` in the same order `gh` does.
+pub fn env_token() -> Option<String> {
+    ["GH_TOKEN", "GITHUB_TOKEN"].iter().find_map(|var| {
+        std::env::var(var)
+            .ok()
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_token_only_for_github_hosts() {
+        assert_eq!(
+            url_host("https://x-access-token@GitHub.com:443/o/r.git").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(url_host("git@github.com:o/r.git"), None);
+
+        let configured = vec!["ghe.example.com".to_string()];
+        assert!(github_host_matches("github.com", None, &[]));
+        assert!(github_host_matches("ghe.example.com", None, &configured));
+        assert!(github_host_matches("ghe.corp", Some("GHE.corp"), &[]));
+        assert!(!github_host_matches("gitlab.com", None, &configured));
+        assert!(!github_host_matches("github.com.evil.example", None, &[]));
+    }
+
+    #[test]
+    fn test_ssh_key_candidates() {
+        let home = TempDir::new().unwrap();
+        let ssh = home.path().join(".ssh");
+        std::fs::create_dir(&ssh).unwrap();
+        std::fs::write(ssh.join("id_rsa"), "").unwrap();
+        std::fs::write(ssh.join("id_ed25519"), "").unwrap();
+        std::fs::write(ssh.join("id_rsa.pub"), "").unwrap();
+
+        assert_eq!(
+            ssh_key_candidates(home.path()),
+            vec![ssh.join("id_ed25519"), ssh.join("id_rsa")]
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{Error, Result};
+use git2::Repository;
+use std::path::PathBuf;
+
+/// Settings merged from gitconfig and `~/.git-utils/config.toml`
+///
+/// Keys use the gitconfig form (`git-repos.root`). In the TOML file the first
+/// component is the table name and the rest is the key, so `git-repos.root`
+/// maps to `root` under `[git-repos]`.
+///
+/// gitconfig takes precedence over the TOML file, so values can still be
+/// overridden per repository with `git config --local`.
+pub struct Config {
+    git: Option<git2::Config>,
+    toml: Option<toml::Table>,
+}
+
+impl Config {
+    /// Load the global gitconfig and the TOML config file
+    pub fn load() -> Result<Self> {
+        Ok(Self {
+            git: git2::Config::open_default().ok(),
+            toml: load_toml()?,
+        })
+    }
+
+    /// Load the repository's gitconfig (including global) and the TOML config file
+    pub fn load_for_repo(repo: &Repository) -> Result<Self> {
+        Ok(Self {
+            git: repo.config().ok(),
+            toml: load_toml()?,
+        })
+    }
+
+    /// Get a string value
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        if let Some(value) = self.git.as_ref().and_then(|c| c.get_string(key).ok()) {
+            return Some(value);
+        }
+        self.toml_value(key)?.as_str().map(|s| s.to_string())
+    }
+
+    /// Get a boolean value
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        if let Some(value) = self.git.as_ref().and_then(|c| c.get_bool(key).ok()) {
+            return Some(value);
+        }
+        self.toml_value(key)?.as_bool()
+    }
+
+    /// Get an integer value
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        if let Some(value) = self.git.as_ref().and_then(|c| c.get_i64(key).ok()) {
+            return Some(value);
+        }
+        self.toml_value(key)?.as_integer()
+    }
+
+    /// Get a list value
+    ///
+    /// In gitconfig, lists are either a multi-valued key or a comma-separated
+    /// string. In TOML, lists are arrays of strings (a plain string is also
+    /// split on commas).
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        if let Some(config) = &self.git {
+            let mut values = Vec::new();
+            if let Ok(mut entries) = config.multivar(key, None) {
+                while let Some(Ok(entry)) = entries.next() {
+                    if let Some(value) = entry.value() {
+                        values.extend(split_list(value));
+                    }
+                }
+            }
+            if !values.is_empty() {
+                return values;
+            }
+        }
+
+        match self.toml_value(key) {
+            Some(toml::Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_string())
+                .collect(),
+            Some(toml::Value::String(s)) => split_list(s),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get all list values under a subsection, in definition order
+    ///
+    /// For example `get_list_map("git-pr-merged.section")` returns
+    /// `("features", ["feature", "enhancement"])` for
+    /// `git-pr-merged.section.features = feature,enhancement`.
+    pub fn get_list_map(&self, prefix: &str) -> Vec<(String, Vec<String>)> {
+        let mut map: Vec<(String, Vec<String>)> = Vec::new();
+
+        if let Some(config) = &self.git {
+            let pattern = format!("^{}\\.", prefix.replace('.', "\\."));
+            if let Ok(mut entries) = config.entries(Some(&pattern)) {
+                while let Some(Ok(entry)) = entries.next() {
+                    let (Some(name), Some(value)) = (entry.name(), entry.value()) else {
+                        continue;
+                    };
+                    let key = name[prefix.len() + 1..].to_string();
+                    match map.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, values)) => values.extend(split_list(value)),
+                        None => map.push((key, split_list(value))),
+                    }
+                }
+            }
+        }
+        if !map.is_empty() {
+            return map;
+        }
+
+        if let Some(toml::Value::Table(table)) = self.toml_value(prefix) {
+            for (key, value) in table {
+                let values = match value {
+                    toml::Value::Array(items) => items
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .collect(),
+                    toml::Value::String(s) => split_list(s),
+                    _ => continue,
+                };
+                map.push((key.clone(), values));
+            }
+        }
+
+        map
+    }
+
+    fn toml_value(&self, key: &str) -> Option<&toml::Value> {
+        let (section, rest) = key.split_once('.')?;
+        let mut value = self.toml.as_ref()?.get(section)?;
+        for part in rest.split('.') {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(value)
+    }
+}
+
+/// The suite's directory for config and logs (`~/.git-utils`)
+pub fn git_utils_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".git-utils"))
+}
+
+/// Path to the TOML config file (`~/.git-utils/config.toml`)
+pub fn config_file_path() -> Option<PathBuf> {
+    git_utils_dir().map(|dir| dir.join("config.toml"))
+}
+
+fn load_toml() -> Result<Option<toml::Table>> {
+    let Some(path) = config_file_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&path)?;
+    let table = content
+        .parse::<toml::Table>()
+        .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+    Ok(Some(table))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_toml(content: &str) -> Config {
+        Config {
+            git: None,
+            toml: Some(content.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_toml_lookup() {
+        let config = from_toml(
+            r#"
+            [git-repos]
+            root = "~/src"
+            prefer-ssh = true
+
+            [git-repos.clone]
+            depth = 50
+
+            [git-branch-delete]
+            protected = ["main", "release/*"]
+
+            [git-pr-merged.section]
+            features = "feature, enhancement"
+            "#,
+        );
+
+        assert_eq!(
+            config.get_string("git-repos.root").as_deref(),
+            Some("~/src")
+        );
+        assert_eq!(config.get_bool("git-repos.prefer-ssh"), Some(true));
+        assert_eq!(config.get_int("git-repos.clone.depth"), Some(50));
+        assert_eq!(
+            config.get_list("git-branch-delete.protected"),
+            vec!["main", "release/*"]
+        );
+        assert_eq!(
+            config.get_list("git-pr-merged.section.features"),
+            vec!["feature", "enhancement"]
+        );
+        assert_eq!(config.get_string("git-repos.missing"), None);
+        assert_eq!(
+            config.get_list_map("git-pr-merged.section"),
+            vec![(
+                "features".to_string(),
+                vec!["feature".to_string(), "enhancement".to_string()]
+            )]
+        );
+    }
+}
@@ -14,6 +14,16 @@ pub enum Error {
     #[error("Base branch not found. Please configure git-branch-delete.base in .gitconfig")]
     BaseBranchNotFound,
 
+    #[error("Failed to switch to branch '{branch}' ({step}): {source}")]
+    SwitchBranch {
+        branch: String,
+        step: SwitchStep,
+        source: git2::Error,
+    },
+
+    #[error("Config error: {0}")]
+    Config(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -21,4 +31,29 @@ pub enum Error {
     Other(String),
 }
 
+/// The step of a branch switch that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchStep {
+    /// Resolving the target branch
+    Resolve,
+    /// Updating the index and working tree
+    Checkout,
+    /// Updating HEAD (the working tree was restored)
+    SetHead,
+    /// Restoring the working tree after a failed HEAD update
+    Rollback,
+}
+
+impl std::fmt::Display for SwitchStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let step = match self {
+            SwitchStep::Resolve => "resolving branch",
+            SwitchStep::Checkout => "checking out tree",
+            SwitchStep::SetHead => "updating HEAD; working tree restored",
+            SwitchStep::Rollback => "restoring working tree; repository may be inconsistent",
+        };
+        f.write_str(step)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
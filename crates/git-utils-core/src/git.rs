@@ -1,6 +1,14 @@
-use crate::{Error, Result};
-use git2::{BranchType, Repository};
-use std::path::Path;
+use crate::config::Config;
+use crate::{Error, Result, SwitchStep};
+use git2::build::CheckoutBuilder;
+use git2::{
+    BranchType, Commit, Delta, DescribeFormatOptions, DescribeOptions, Repository, StatusOptions,
+    Tree,
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 /// Opens the git repository in the current directory or parent directories
 pub fn open_repo() -> Result<Repository> {
@@ -32,44 +40,568 @@ pub fn get_current_branch(repo: &Repository) -> Result<String> {
     }
 }
 
+/// Resolve the commit a base refers to
+///
+/// Local branches are tried first, then remote-tracking branches (e.g. `origin/main`),
+/// then any revision such as a tag or commit.
+pub fn resolve_base_commit<'repo>(repo: &'repo Repository, base: &str) -> Result<Commit<'repo>> {
+    if let Ok(branch) = repo.find_branch(base, BranchType::Local) {
+        return Ok(branch.get().peel_to_commit()?);
+    }
+    if let Ok(branch) = repo.find_branch(base, BranchType::Remote) {
+        return Ok(branch.get().peel_to_commit()?);
+    }
+    repo.revparse_single(base)
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(|_| Error::BranchNotFound(base.to_string()))
+}
+
 /// Check if a branch is merged into the base branch
+///
+/// The base may be a local branch, a remote-tracking branch such as `origin/main`,
+/// or any other revision such as a tag.
 pub fn is_branch_merged(repo: &Repository, branch_name: &str, base_branch: &str) -> Result<bool> {
-    let base_ref = repo.find_branch(base_branch, BranchType::Local)?;
-    let base_commit = base_ref.get().peel_to_commit()?;
+    let base_commit = resolve_base_commit(repo, base_branch)?;
 
     let branch_ref = repo.find_branch(branch_name, BranchType::Local)?;
     let branch_commit = branch_ref.get().peel_to_commit()?;
 
-    Ok(repo.graph_descendant_of(base_commit.id(), branch_commit.id())?)
+    // Merged when the base contains the branch tip: either the same commit or
+    // a descendant of it (graph_descendant_of is strict)
+    Ok(base_commit.id() == branch_commit.id()
+        || repo.graph_descendant_of(base_commit.id(), branch_commit.id())?)
 }
 
-/// Detect base branch (main, master, or develop)
-pub fn detect_base_branch(repo: &Repository) -> Result<String> {
-    // First, check git config
-    if let Ok(config) = repo.config() {
-        if let Ok(base) = config.get_string("git-branch-delete.base") {
-            return Ok(base);
+/// Check if every commit on a branch has a patch-equivalent commit in the base
+///
+/// This is the same check as `git cherry`: it catches branches that were
+/// rebased or cherry-picked onto the base rather than merged. Merge commits on
+/// the branch are ignored.
+pub fn is_branch_cherry_merged(
+    repo: &Repository,
+    branch_name: &str,
+    base_branch: &str,
+) -> Result<bool> {
+    let checker = MergeChecker::new(repo, base_branch, MergeStrategy::Cherry)?;
+    let (branch_commit, merge_base) = checker.branch_commit(branch_name)?;
+    checker.is_cherry_merged(&branch_commit, merge_base)
+}
+
+/// Check if a branch was squash-merged into the base
+///
+/// A squash merge lands the branch's combined change since the merge base as a
+/// single commit, so this compares the patch ID of that combined diff with the
+/// base's commits since the merge base (`git cherry` against a virtual
+/// squashed commit). Branches with no net change are not considered squashed.
+pub fn is_branch_squash_merged(
+    repo: &Repository,
+    branch_name: &str,
+    base_branch: &str,
+) -> Result<bool> {
+    let checker = MergeChecker::new(repo, base_branch, MergeStrategy::Both)?;
+    let (branch_commit, merge_base) = checker.branch_commit(branch_name)?;
+    checker.is_squash_merged(&branch_commit, merge_base)
+}
+
+/// Merge status checks of many branches against one base
+///
+/// The base is resolved once, and the patch IDs of the base's commits since a
+/// merge base are computed once per merge base and shared by the cherry and
+/// squash checks of every branch, so checking all local branches doesn't walk
+/// and diff the base history again for each one.
+pub struct MergeChecker<'repo> {
+    repo: &'repo Repository,
+    base: Commit<'repo>,
+    strategy: MergeStrategy,
+    base_patches: RefCell<HashMap<git2::Oid, Rc<HashSet<git2::Oid>>>>,
+}
+
+impl<'repo> MergeChecker<'repo> {
+    pub fn new(
+        repo: &'repo Repository,
+        base_branch: &str,
+        strategy: MergeStrategy,
+    ) -> Result<Self> {
+        Ok(Self {
+            repo,
+            base: resolve_base_commit(repo, base_branch)?,
+            strategy,
+            base_patches: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Merged per the strategy, else squash-merged, else unmerged
+    pub fn status(&self, branch_name: &str) -> Result<MergeStatus> {
+        let (branch_commit, merge_base) = self.branch_commit(branch_name)?;
+
+        // Merged when the base contains the branch tip (graph_descendant_of is strict)
+        let contained = merge_base == branch_commit.id();
+        let merged = match self.strategy {
+            MergeStrategy::Ancestor => contained,
+            MergeStrategy::Cherry => self.is_cherry_merged(&branch_commit, merge_base)?,
+            MergeStrategy::Both => {
+                contained || self.is_cherry_merged(&branch_commit, merge_base)?
+            }
+        };
+
+        if merged {
+            Ok(MergeStatus::Merged)
+        } else if self.is_squash_merged(&branch_commit, merge_base)? {
+            Ok(MergeStatus::Squashed)
+        } else {
+            Ok(MergeStatus::Unmerged)
+        }
+    }
+
+    /// A local branch's tip and its merge base with the base
+    fn branch_commit(&self, branch_name: &str) -> Result<(Commit<'repo>, git2::Oid)> {
+        let branch_commit = self
+            .repo
+            .find_branch(branch_name, BranchType::Local)?
+            .get()
+            .peel_to_commit()?;
+        let merge_base = self.repo.merge_base(self.base.id(), branch_commit.id())?;
+        Ok((branch_commit, merge_base))
+    }
+
+    fn is_cherry_merged(&self, branch_commit: &Commit, merge_base: git2::Oid) -> Result<bool> {
+        let branch_patches = patch_ids_since(self.repo, branch_commit.id(), merge_base)?;
+        if branch_patches.is_empty() {
+            return Ok(true);
+        }
+        let base_patches = self.base_patches_since(merge_base)?;
+        Ok(branch_patches.iter().all(|id| base_patches.contains(id)))
+    }
+
+    fn is_squash_merged(&self, branch_commit: &Commit, merge_base: git2::Oid) -> Result<bool> {
+        let base_tree = self.repo.find_commit(merge_base)?.tree()?;
+        let diff =
+            self.repo
+                .diff_tree_to_tree(Some(&base_tree), Some(&branch_commit.tree()?), None)?;
+        if diff.deltas().len() == 0 {
+            return Ok(false);
+        }
+        let squashed = diff.patchid(None)?;
+        Ok(self.base_patches_since(merge_base)?.contains(&squashed))
+    }
+
+    fn base_patches_since(&self, merge_base: git2::Oid) -> Result<Rc<HashSet<git2::Oid>>> {
+        if let Some(patches) = self.base_patches.borrow().get(&merge_base) {
+            return Ok(Rc::clone(patches));
         }
+        let patches = Rc::new(patch_ids_since(self.repo, self.base.id(), merge_base)?);
+        self.base_patches
+            .borrow_mut()
+            .insert(merge_base, Rc::clone(&patches));
+        Ok(patches)
     }
+}
+
+/// Patch IDs of the non-merge commits reachable from `tip` but not from `since`
+fn patch_ids_since(
+    repo: &Repository,
+    tip: git2::Oid,
+    since: git2::Oid,
+) -> Result<HashSet<git2::Oid>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip)?;
+    revwalk.hide(since)?;
+
+    let mut ids = HashSet::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() != 1 {
+            continue;
+        }
+        let parent_tree = commit.parent(0)?.tree()?;
+        let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit.tree()?), None)?;
+        ids.insert(diff.patchid(None)?);
+    }
+    Ok(ids)
+}
+
+/// How to decide whether a branch has been merged into the base
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The branch tip is an ancestor of the base
+    Ancestor,
+    /// Every branch commit has a patch-equivalent commit in the base
+    Cherry,
+    /// Merged if either check says so
+    Both,
+}
+
+impl std::str::FromStr for MergeStrategy {
+    type Err = Error;
 
-    // Try common base branch names
-    for candidate in &["main", "master", "develop"] {
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ancestor" => Ok(MergeStrategy::Ancestor),
+            "cherry" => Ok(MergeStrategy::Cherry),
+            "both" => Ok(MergeStrategy::Both),
+            other => Err(Error::Config(format!(
+                "Invalid git-branch-delete.merge-strategy '{}' (expected ancestor, cherry, or both)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Read the merge strategy from `git-branch-delete.merge-strategy` (default: both)
+pub fn merge_strategy(repo: &Repository) -> Result<MergeStrategy> {
+    match Config::load_for_repo(repo)?.get_string("git-branch-delete.merge-strategy") {
+        Some(value) => value.parse(),
+        None => Ok(MergeStrategy::Both),
+    }
+}
+
+/// Check if a branch is merged into the base using the given strategy
+pub fn is_branch_merged_with(
+    repo: &Repository,
+    branch_name: &str,
+    base_branch: &str,
+    strategy: MergeStrategy,
+) -> Result<bool> {
+    match strategy {
+        MergeStrategy::Ancestor => is_branch_merged(repo, branch_name, base_branch),
+        MergeStrategy::Cherry => is_branch_cherry_merged(repo, branch_name, base_branch),
+        MergeStrategy::Both => Ok(is_branch_merged(repo, branch_name, base_branch)?
+            || is_branch_cherry_merged(repo, branch_name, base_branch)?),
+    }
+}
+
+/// Whether (and how) a branch has landed in the base
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStatus {
+    /// Merged according to the merge strategy
+    Merged,
+    /// Squash-merged: the branch's combined change is a single commit on the base
+    Squashed,
+    Unmerged,
+}
+
+impl MergeStatus {
+    pub fn is_merged(self) -> bool {
+        self != MergeStatus::Unmerged
+    }
+}
+
+/// Check a branch with the given strategy, falling back to squash-merge detection
+///
+/// Use a [`MergeChecker`] to check several branches against the same base.
+pub fn branch_merge_status(
+    repo: &Repository,
+    branch_name: &str,
+    base_branch: &str,
+    strategy: MergeStrategy,
+) -> Result<MergeStatus> {
+    MergeChecker::new(repo, base_branch, strategy)?.status(branch_name)
+}
+
+/// Committer time of a local branch's tip, in seconds since the Unix epoch
+pub fn get_branch_commit_time(repo: &Repository, branch_name: &str) -> Result<i64> {
+    let branch = repo.find_branch(branch_name, BranchType::Local)?;
+    Ok(branch.get().peel_to_commit()?.time().seconds())
+}
+
+/// Time and author of a branch's last commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSummary {
+    /// Committer time, in seconds since the Unix epoch
+    pub time: i64,
+    /// Author name (empty if it isn't valid UTF-8)
+    pub author: String,
+}
+
+/// Summary of a local branch's tip commit
+pub fn get_branch_tip_summary(repo: &Repository, branch_name: &str) -> Result<CommitSummary> {
+    let branch = repo.find_branch(branch_name, BranchType::Local)?;
+    let commit = branch.get().peel_to_commit()?;
+    let author = commit.author().name().unwrap_or_default().to_string();
+    Ok(CommitSummary {
+        time: commit.time().seconds(),
+        author,
+    })
+}
+
+/// Get the most recent tag reachable from HEAD (like `git describe --tags --abbrev=0`)
+pub fn get_latest_tag(repo: &Repository) -> Option<String> {
+    let describe = repo.describe(DescribeOptions::new().describe_tags()).ok()?;
+    describe
+        .format(Some(DescribeFormatOptions::new().abbreviated_size(0)))
+        .ok()
+}
+
+/// Tags named like semantic versions (`1.2.3` or `v1.2.3`, optionally with a
+/// `-prerelease` suffix) that are reachable from HEAD, oldest version first
+///
+/// Other tags are ignored. A pre-release sorts before its release.
+pub fn get_version_tags(repo: &Repository) -> Result<Vec<String>> {
+    let head = repo.head()?.peel_to_commit()?.id();
+
+    let mut tags = Vec::new();
+    for name in repo.tag_names(None)?.iter().flatten() {
+        let Some(version) = parse_version(name) else {
+            continue;
+        };
+        let commit = repo.revparse_single(name)?.peel_to_commit()?.id();
+        if commit == head || repo.graph_descendant_of(head, commit)? {
+            tags.push((version, name.to_string()));
+        }
+    }
+
+    tags.sort();
+    Ok(tags.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Sort key for a version tag: (major, minor, patch, is-release, pre-release)
+type VersionKey = (u64, u64, u64, bool, String);
+
+fn parse_version(tag: &str) -> Option<VersionKey> {
+    let version = tag.strip_prefix('v').unwrap_or(tag);
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+
+    let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+    let (major, minor, patch) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((
+        major,
+        minor,
+        patch,
+        pre.is_none(),
+        pre.unwrap_or_default().to_string(),
+    ))
+}
+
+/// Default base branch candidates, in order of preference
+const DEFAULT_BASE_CANDIDATES: [&str; 3] = ["main", "master", "develop"];
+
+/// Detect base branch
+///
+/// Tries, in order: the `git-branch-delete.base` setting, the candidate list
+/// (`git-branch-delete.base-candidates`, default main, master, develop) as local
+/// branches and then on `origin`, and finally the branch `origin/HEAD` points to.
+pub fn detect_base_branch(repo: &Repository) -> Result<String> {
+    // First, check configuration (gitconfig or config.toml)
+    let config = Config::load_for_repo(repo)?;
+    if let Some(base) = config.get_string("git-branch-delete.base") {
+        return Ok(base);
+    }
+
+    let mut candidates = config.get_list("git-branch-delete.base-candidates");
+    if candidates.is_empty() {
+        candidates = DEFAULT_BASE_CANDIDATES.map(String::from).to_vec();
+    }
+
+    // Try candidate base branch names
+    for candidate in &candidates {
         if repo.find_branch(candidate, BranchType::Local).is_ok() {
             return Ok(candidate.to_string());
         }
     }
 
-    Err(Error::BaseBranchNotFound)
+    // Fall back to remote-tracking branches (e.g. fresh clone)
+    for candidate in &candidates {
+        let remote_branch = format!("origin/{}", candidate);
+        if repo.find_branch(&remote_branch, BranchType::Remote).is_ok() {
+            return Ok(remote_branch);
+        }
+    }
+
+    // Finally, use the remote's default branch
+    get_origin_head(repo).ok_or(Error::BaseBranchNotFound)
+}
+
+/// The remote-tracking branch `refs/remotes/origin/HEAD` points to (e.g. `origin/main`)
+pub fn get_origin_head(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("refs/remotes/origin/HEAD").ok()?;
+    let name = head.symbolic_target()?.strip_prefix("refs/remotes/")?;
+    repo.find_branch(name, BranchType::Remote).ok()?;
+    Some(name.to_string())
+}
+
+/// The repository's default branch
+///
+/// Prefers the branch `origin/HEAD` points to, falling back to
+/// [`detect_base_branch`] when it isn't set (e.g. no remote, or a remote
+/// added without `git remote set-head`).
+pub fn get_default_branch(repo: &Repository) -> Option<String> {
+    get_origin_head(repo).or_else(|| detect_base_branch(repo).ok())
+}
+
+/// Get the ahead/behind commit counts of a local branch relative to its upstream
+///
+/// Returns `None` if the branch has no upstream configured.
+pub fn ahead_behind(repo: &Repository, branch_name: &str) -> Result<Option<(usize, usize)>> {
+    let branch = repo.find_branch(branch_name, BranchType::Local)?;
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let local_oid = branch.get().peel_to_commit()?.id();
+    let upstream_oid = upstream.get().peel_to_commit()?.id();
+
+    Ok(Some(repo.graph_ahead_behind(local_oid, upstream_oid)?))
+}
+
+/// Find another worktree (the main one or a linked one) that has the branch checked out
+///
+/// The worktree `repo` was opened in is skipped, since switching there is
+/// the normal case.
+pub fn find_worktree_for_branch(repo: &Repository, branch_name: &str) -> Result<Option<PathBuf>> {
+    let current = repo.workdir().and_then(|path| path.canonicalize().ok());
+    let is_current = |path: &Path| current.is_some() && path.canonicalize().ok() == current;
+    let has_branch = |wt_repo: &Repository| {
+        wt_repo
+            .head()
+            .is_ok_and(|head| head.is_branch() && head.shorthand() == Some(branch_name))
+    };
+
+    // The main worktree isn't listed among `worktrees()`
+    if let Ok(main) = Repository::open(repo.commondir()) {
+        if let Some(path) = main.workdir() {
+            if !is_current(path) && has_branch(&main) {
+                return Ok(Some(path.to_path_buf()));
+            }
+        }
+    }
+
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        if is_current(worktree.path()) {
+            continue;
+        }
+        let Ok(wt_repo) = Repository::open_from_worktree(&worktree) else {
+            continue;
+        };
+        if has_branch(&wt_repo) {
+            return Ok(Some(worktree.path().to_path_buf()));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether tracked files have staged or unstaged changes
+///
+/// Untracked files are ignored, as they don't block a branch switch unless
+/// the target branch tracks the same path.
+pub fn has_uncommitted_changes(repo: &Repository) -> Result<bool> {
+    if repo.is_bare() {
+        return Ok(false);
+    }
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    Ok(!repo.statuses(Some(&mut options))?.is_empty())
+}
+
+/// Stash uncommitted changes to tracked files, like `git stash push -m <message>`
+///
+/// Stashing needs a mutable repository, so this reopens it rather than asking
+/// callers to hold a `&mut Repository`.
+pub fn stash_changes(repo: &Repository, message: &str) -> Result<()> {
+    let mut repo = Repository::open(repo.path())?;
+    let signature = repo.signature()?;
+    repo.stash_save(&signature, message, None)?;
+    Ok(())
 }
 
 /// Switch to a branch
+///
+/// The index and working tree are updated first, then HEAD. If updating HEAD
+/// fails, the working tree is restored so the repository is left unchanged.
 pub fn switch_branch(repo: &Repository, branch_name: &str) -> Result<()> {
-    let obj = repo.revparse_single(&format!("refs/heads/{}", branch_name))?;
-    repo.checkout_tree(&obj, None)?;
-    repo.set_head(&format!("refs/heads/{}", branch_name))?;
+    switch_branch_with(repo, branch_name, false, |repo, refname| {
+        repo.set_head(refname)
+    })
+}
+
+/// Switch to a branch, discarding local changes that would block the checkout
+pub fn force_switch_branch(repo: &Repository, branch_name: &str) -> Result<()> {
+    switch_branch_with(repo, branch_name, true, |repo, refname| {
+        repo.set_head(refname)
+    })
+}
+
+fn switch_branch_with<F>(
+    repo: &Repository,
+    branch_name: &str,
+    force: bool,
+    set_head: F,
+) -> Result<()>
+where
+    F: FnOnce(&Repository, &str) -> std::result::Result<(), git2::Error>,
+{
+    let step_error = |step, source| Error::SwitchBranch {
+        branch: branch_name.to_string(),
+        step,
+        source,
+    };
+
+    let refname = format!("refs/heads/{}", branch_name);
+    let target = repo
+        .revparse_single(&refname)
+        .map_err(|e| step_error(SwitchStep::Resolve, e))?;
+    let target_tree = target
+        .peel_to_tree()
+        .map_err(|e| step_error(SwitchStep::Resolve, e))?;
+    let original_tree = repo.head().and_then(|head| head.peel_to_tree()).ok();
+
+    let mut checkout = CheckoutBuilder::new();
+    if force {
+        checkout.force();
+    }
+    repo.checkout_tree(&target, Some(&mut checkout))
+        .map_err(|e| step_error(SwitchStep::Checkout, e))?;
+
+    if let Err(e) = set_head(repo, &refname) {
+        if let Some(original_tree) = &original_tree {
+            restore_tree(repo, &target_tree, original_tree)
+                .map_err(|e| step_error(SwitchStep::Rollback, e))?;
+        }
+        return Err(step_error(SwitchStep::SetHead, e));
+    }
+
     Ok(())
 }
 
+/// Undo a checkout of `from` by checking out `to`, touching only the paths
+/// that differ between the two trees so other local changes are preserved
+fn restore_tree(repo: &Repository, from: &Tree, to: &Tree) -> std::result::Result<(), git2::Error> {
+    let diff = repo.diff_tree_to_tree(Some(from), Some(to), None)?;
+    if diff.deltas().len() == 0 {
+        return Ok(());
+    }
+
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force();
+
+    let mut index = repo.index()?;
+    for delta in diff.deltas() {
+        let Some(path) = delta.old_file().path().or(delta.new_file().path()) else {
+            continue;
+        };
+        if delta.status() == Delta::Deleted {
+            // Only in `from`: checkout of `to` won't remove it, so do it here
+            if let Some(workdir) = repo.workdir() {
+                let _ = std::fs::remove_file(workdir.join(path));
+            }
+            index.remove_path(path)?;
+        } else {
+            checkout.path(path);
+        }
+    }
+    index.write()?;
+
+    repo.checkout_tree(to.as_object(), Some(&mut checkout))
+}
+
 /// Delete a branch
 pub fn delete_branch(repo: &Repository, branch_name: &str, force: bool) -> Result<()> {
     let mut branch = repo.find_branch(branch_name, BranchType::Local)?;
@@ -77,7 +609,8 @@ pub fn delete_branch(repo: &Repository, branch_name: &str, force: bool) -> Resul
     // Check if merged before deleting (unless force is true)
     if !force {
         let base_branch = detect_base_branch(repo)?;
-        if !is_branch_merged(repo, branch_name, &base_branch)? {
+        let strategy = merge_strategy(repo)?;
+        if !branch_merge_status(repo, branch_name, &base_branch, strategy)?.is_merged() {
             return Err(Error::Other(format!(
                 "Branch '{}' is not merged into '{}'. Use --force to delete anyway.",
                 branch_name, base_branch
@@ -89,19 +622,235 @@ pub fn delete_branch(repo: &Repository, branch_name: &str, force: bool) -> Resul
     Ok(())
 }
 
-/// Get recent branches from reflog
-pub fn get_recent_branches(repo: &Repository) -> Result<Vec<String>> {
+/// Find the remote branch corresponding to a local branch
+///
+/// Uses the configured upstream if there is one, otherwise falls back to a
+/// branch with the same name on `origin`. Returns `(remote, branch)`.
+pub fn find_remote_branch(
+    repo: &Repository,
+    branch_name: &str,
+) -> Result<Option<(String, String)>> {
+    let branch = repo.find_branch(branch_name, BranchType::Local)?;
+
+    if let Ok(upstream) = branch.upstream() {
+        if let Some(upstream_ref) = upstream.get().name() {
+            let remote = repo.branch_remote_name(upstream_ref)?;
+            if let Some(remote) = remote.as_str() {
+                let prefix = format!("refs/remotes/{}/", remote);
+                if let Some(name) = upstream_ref.strip_prefix(&prefix) {
+                    return Ok(Some((remote.to_string(), name.to_string())));
+                }
+            }
+        }
+    }
+
+    let fallback = format!("origin/{}", branch_name);
+    if repo.find_branch(&fallback, BranchType::Remote).is_ok() {
+        return Ok(Some(("origin".to_string(), branch_name.to_string())));
+    }
+
+    Ok(None)
+}
+
+/// Check if a branch's configured upstream no longer exists
+///
+/// This is `[gone]` in `git branch -vv`: the branch was deleted on the remote
+/// and `git fetch --prune` removed its remote-tracking branch. Branches
+/// without an upstream are never gone.
+pub fn is_upstream_gone(repo: &Repository, branch_name: &str) -> Result<bool> {
+    let refname = format!("refs/heads/{}", branch_name);
+    let upstream = match repo.branch_upstream_name(&refname) {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(upstream
+        .as_str()
+        .is_some_and(|upstream| repo.find_reference(upstream).is_err()))
+}
+
+/// Remove remote-tracking branches that no longer exist on the remote
+///
+/// The equivalent of `git remote prune <remote>`: connects to the remote, lists
+/// the refs it advertises and deletes tracking refs (found through the fetch
+/// refspecs) for the rest. Returns the pruned names, e.g. `origin/feature`;
+/// with `dry_run` nothing is deleted.
+pub fn prune_remote(repo: &Repository, remote_name: &str, dry_run: bool) -> Result<Vec<String>> {
+    let mut remote = repo.find_remote(remote_name)?;
+    let advertised: HashSet<String> = {
+        let connection = remote.connect_auth(
+            git2::Direction::Fetch,
+            Some(crate::auth::remote_callbacks()),
+            None,
+        )?;
+        connection
+            .list()?
+            .iter()
+            .map(|head| head.name().to_string())
+            .collect()
+    };
+
+    let mut stale = Vec::new();
+    for reference in repo.references()? {
+        let reference = reference?;
+        // Symbolic refs such as origin/HEAD follow their target
+        if reference.kind() != Some(git2::ReferenceType::Direct) {
+            continue;
+        }
+        let Some(name) = reference.name() else {
+            continue;
+        };
+        let source = remote
+            .refspecs()
+            .filter(|spec| spec.direction() == git2::Direction::Fetch && spec.dst_matches(name))
+            .find_map(|spec| spec.rtransform(name).ok());
+        if let Some(source) = source.as_ref().and_then(|s| s.as_str()) {
+            if !advertised.contains(source) {
+                stale.push(name.to_string());
+            }
+        }
+    }
+
+    let mut pruned = Vec::new();
+    for name in stale {
+        if !dry_run {
+            repo.find_reference(&name)?.delete()?;
+        }
+        pruned.push(
+            name.strip_prefix("refs/remotes/")
+                .unwrap_or(&name)
+                .to_string(),
+        );
+    }
+    Ok(pruned)
+}
+
+/// Get remote branches that no local branch tracks
+///
+/// Returns `(remote, branch)` pairs, e.g. `("origin", "feature/x")`. Symbolic
+/// refs such as `origin/HEAD` are skipped.
+pub fn get_remote_only_branches(repo: &Repository) -> Result<Vec<(String, String)>> {
+    let mut tracked = HashSet::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Ok(upstream) = branch.upstream() {
+            if let Some(name) = upstream.get().name() {
+                tracked.insert(name.to_string());
+            }
+        }
+    }
+
+    let mut branches = Vec::new();
+    for branch in repo.branches(Some(BranchType::Remote))? {
+        let (branch, _) = branch?;
+        let reference = branch.get();
+        if reference.symbolic_target().is_some() {
+            continue;
+        }
+        let Some(refname) = reference.name() else {
+            continue;
+        };
+        if tracked.contains(refname) {
+            continue;
+        }
+        let remote = repo.branch_remote_name(refname)?;
+        if let Some(remote) = remote.as_str() {
+            let prefix = format!("refs/remotes/{}/", remote);
+            if let Some(name) = refname.strip_prefix(&prefix) {
+                branches.push((remote.to_string(), name.to_string()));
+            }
+        }
+    }
+    Ok(branches)
+}
+
+/// Create a local branch at a remote branch and set it as the upstream
+pub fn create_tracking_branch(
+    repo: &Repository,
+    local_name: &str,
+    remote: &str,
+    remote_branch: &str,
+) -> Result<()> {
+    let upstream = format!("{}/{}", remote, remote_branch);
+    let commit = repo
+        .find_branch(&upstream, BranchType::Remote)
+        .map_err(|_| Error::BranchNotFound(upstream.clone()))?
+        .get()
+        .peel_to_commit()?;
+
+    let mut branch = repo.branch(local_name, &commit, false)?;
+    branch.set_upstream(Some(&upstream))?;
+    Ok(())
+}
+
+/// Create a local branch at `base` (default: HEAD) without switching to it
+pub fn create_branch(repo: &Repository, name: &str, base: Option<&str>) -> Result<()> {
+    let base = base.unwrap_or("HEAD");
+    let commit = repo
+        .revparse_single(base)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| Error::BranchNotFound(base.to_string()))?;
+
+    repo.branch(name, &commit, false)?;
+    Ok(())
+}
+
+/// Arguments to `git` that delete a branch on a remote, without running them
+///
+/// Used by dry-run modes to report exactly what `delete_remote_branch` would push.
+pub fn plan_delete_remote_branch(remote: &str, branch_name: &str) -> Vec<String> {
+    vec![
+        "push".to_string(),
+        remote.to_string(),
+        "--delete".to_string(),
+        branch_name.to_string(),
+    ]
+}
+
+/// Delete a branch on a remote with `git push <remote> --delete <branch>`
+pub fn delete_remote_branch(repo: &Repository, remote: &str, branch_name: &str) -> Result<()> {
+    let workdir = get_repo_root(repo)?;
+    let output = std::process::Command::new("git")
+        .args(plan_delete_remote_branch(remote, branch_name))
+        .current_dir(workdir)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Other(format!(
+            "git push --delete failed: {}",
+            stderr.trim()
+        )));
+    }
+
+    Ok(())
+}
+
+/// How many recent branches interactive lists show by default
+pub const DEFAULT_RECENT_LIMIT: usize = 20;
+
+/// Get recent branches from reflog, most recent first
+///
+/// Only names that still exist as local branches are returned, so deleted
+/// branches and detached checkouts (commit ids) are skipped. With a `limit`,
+/// the reflog walk stops once that many branches have been found.
+pub fn get_recent_branches(repo: &Repository, limit: Option<usize>) -> Result<Vec<String>> {
     let mut branches = Vec::new();
-    let mut seen = std::collections::HashSet::new();
+    let mut seen = HashSet::new();
 
     // Parse reflog to find branch switches
     let reflog = repo.reflog("HEAD")?;
     for entry in reflog.iter() {
+        if limit.is_some_and(|limit| branches.len() >= limit) {
+            break;
+        }
         if let Some(msg) = entry.message() {
             if msg.starts_with("checkout: moving from") {
                 // Extract branch name from message like "checkout: moving from main to feature"
                 if let Some(to_branch) = msg.split_whitespace().last() {
-                    if !seen.contains(to_branch) {
+                    if !seen.contains(to_branch)
+                        && repo.find_branch(to_branch, BranchType::Local).is_ok()
+                    {
                         seen.insert(to_branch.to_string());
                         branches.push(to_branch.to_string());
                     }
@@ -113,6 +862,39 @@ pub fn get_recent_branches(repo: &Repository) -> Result<Vec<String>> {
     Ok(branches)
 }
 
+/// Sort branches by their last appearance in the reflog
+///
+/// Recently visited branches come first; branches never visited are placed
+/// last in alphabetical order.
+pub fn sort_branches_by_recent(repo: &Repository, branches: &mut [String]) -> Result<()> {
+    let recent = get_recent_branches(repo, None)?;
+    let rank: HashMap<&str, usize> = recent
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (b.as_str(), i))
+        .collect();
+
+    branches.sort_by(|a, b| match (rank.get(a.as_str()), rank.get(b.as_str())) {
+        (Some(x), Some(y)) => x.cmp(y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.cmp(b),
+    });
+
+    Ok(())
+}
+
+/// Sort branches by their tip's committer time, newest first
+///
+/// Branches with equal times (or whose tip can't be read) keep alphabetical order,
+/// the latter placed last.
+pub fn sort_branches_by_commit_date(repo: &Repository, branches: &mut [String]) {
+    branches.sort_by_cached_key(|b| {
+        let time = get_branch_commit_time(repo, b).ok();
+        (std::cmp::Reverse(time), b.clone())
+    });
+}
+
 /// Get repository root path
 pub fn get_repo_root(repo: &Repository) -> Result<&Path> {
     repo.workdir()
@@ -122,6 +904,53 @@ pub fn get_repo_root(repo: &Repository) -> Result<&Path> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use git2::Signature;
+    use std::sync::OnceLock;
+    use tempfile::TempDir;
+
+    fn init_repo() -> (TempDir, Repository) {
+        isolate_config();
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        (dir, repo)
+    }
+
+    /// Point the global and system gitconfig and `~/.git-utils` at an empty
+    /// directory, so the developer's own settings can't change the results
+    fn isolate_config() {
+        static HOME: OnceLock<TempDir> = OnceLock::new();
+        HOME.get_or_init(|| {
+            let home = TempDir::new().unwrap();
+            for level in [
+                git2::ConfigLevel::System,
+                git2::ConfigLevel::XDG,
+                git2::ConfigLevel::Global,
+                git2::ConfigLevel::ProgramData,
+            ] {
+                // SAFETY: only changes libgit2's config search path, which every
+                // test sets to the same directory
+                unsafe { git2::opts::set_search_path(level, home.path()) }.unwrap();
+            }
+            std::env::set_var("HOME", home.path());
+            home
+        });
+    }
+
+    fn commit(repo: &Repository, refname: &str, parents: &[git2::Oid]) -> git2::Oid {
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parents: Vec<_> = parents
+            .iter()
+            .map(|id| repo.find_commit(*id).unwrap())
+            .collect();
+        let parent_refs: Vec<_> = parents.iter().collect();
+        let oid = repo
+            .commit(None, &sig, &sig, "commit", &tree, &parent_refs)
+            .unwrap();
+        repo.reference(refname, oid, true, "test").unwrap();
+        oid
+    }
 
     #[test]
     fn test_open_repo() {
@@ -131,4 +960,484 @@ mod tests {
             assert!(!repo.is_bare());
         }
     }
+
+    #[test]
+    fn test_detect_base_branch_remote_only() {
+        let (_dir, repo) = init_repo();
+        let base = commit(&repo, "refs/remotes/origin/main", &[]);
+        let feature = commit(&repo, "refs/heads/feature", &[base]);
+        commit(&repo, "refs/remotes/origin/main", &[feature]);
+
+        assert_eq!(detect_base_branch(&repo).unwrap(), "origin/main");
+        assert!(is_branch_merged(&repo, "feature", "origin/main").unwrap());
+    }
+
+    #[test]
+    fn test_sort_branches_by_commit_date() {
+        let (_dir, repo) = init_repo();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        for (name, time) in [
+            ("old", 100),
+            ("new", 300),
+            ("middle", 200),
+            ("also-new", 300),
+        ] {
+            let sig =
+                Signature::new("test", "test@example.com", &git2::Time::new(time, 0)).unwrap();
+            let oid = repo.commit(None, &sig, &sig, name, &tree, &[]).unwrap();
+            repo.reference(&format!("refs/heads/{}", name), oid, true, "test")
+                .unwrap();
+        }
+
+        let mut branches: Vec<String> = ["old", "missing", "new", "middle", "also-new"]
+            .iter()
+            .map(|b| b.to_string())
+            .collect();
+        sort_branches_by_commit_date(&repo, &mut branches);
+        assert_eq!(
+            branches,
+            vec!["also-new", "new", "middle", "old", "missing"]
+        );
+    }
+
+    #[test]
+    fn test_create_branch() {
+        let (_dir, repo) = init_repo();
+        let first = commit(&repo, "HEAD", &[]);
+        let second = commit(&repo, "HEAD", &[first]);
+
+        create_branch(&repo, "feature/x", None).unwrap();
+        create_branch(&repo, "feature/y", Some(&first.to_string())).unwrap();
+
+        let tip = |name| {
+            repo.find_branch(name, BranchType::Local)
+                .unwrap()
+                .get()
+                .target()
+                .unwrap()
+        };
+        assert_eq!(tip("feature/x"), second);
+        assert_eq!(tip("feature/y"), first);
+        assert!(create_branch(&repo, "feature/x", None).is_err());
+        assert!(create_branch(&repo, "feature/z", Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_create_tracking_branch_from_remote_only() {
+        let (_dir, repo) = init_repo();
+        repo.remote("origin", "https://example.com/o/r.git")
+            .unwrap();
+        let base = commit(&repo, "refs/remotes/origin/main", &[]);
+        commit(&repo, "refs/remotes/origin/feature/x", &[base]);
+        repo.branch("main", &repo.find_commit(base).unwrap(), false)
+            .unwrap()
+            .set_upstream(Some("origin/main"))
+            .unwrap();
+
+        let remote_only = get_remote_only_branches(&repo).unwrap();
+        assert_eq!(
+            remote_only,
+            vec![("origin".to_string(), "feature/x".to_string())]
+        );
+
+        create_tracking_branch(&repo, "feature/x", "origin", "feature/x").unwrap();
+        assert!(get_remote_only_branches(&repo).unwrap().is_empty());
+        assert_eq!(
+            find_remote_branch(&repo, "feature/x").unwrap(),
+            Some(("origin".to_string(), "feature/x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_detect_base_branch_trunk() {
+        let (_dir, repo) = init_repo();
+        let base = commit(&repo, "refs/heads/trunk", &[]);
+        commit(&repo, "refs/heads/feature", &[base]);
+        assert!(detect_base_branch(&repo).is_err());
+
+        repo.config()
+            .unwrap()
+            .set_str("git-branch-delete.base-candidates", "stable,trunk")
+            .unwrap();
+        assert_eq!(detect_base_branch(&repo).unwrap(), "trunk");
+    }
+
+    #[test]
+    fn test_detect_base_branch_remote_head() {
+        let (_dir, repo) = init_repo();
+        commit(&repo, "refs/remotes/origin/trunk", &[]);
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/trunk",
+            true,
+            "test",
+        )
+        .unwrap();
+
+        assert_eq!(detect_base_branch(&repo).unwrap(), "origin/trunk");
+
+        // origin/HEAD wins over local candidates for the default branch
+        commit(&repo, "refs/heads/main", &[]);
+        assert_eq!(detect_base_branch(&repo).unwrap(), "main");
+        assert_eq!(get_default_branch(&repo).unwrap(), "origin/trunk");
+    }
+
+    fn commit_file(
+        repo: &Repository,
+        refname: &str,
+        files: &[(&str, &str)],
+        parent: Option<git2::Oid>,
+    ) -> git2::Oid {
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        let mut builder = repo.treebuilder(None).unwrap();
+        for (name, content) in files {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            builder.insert(name, blob, 0o100644).unwrap();
+        }
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let parents: Vec<_> = parent
+            .iter()
+            .map(|id| repo.find_commit(*id).unwrap())
+            .collect();
+        let parent_refs: Vec<_> = parents.iter().collect();
+        let oid = repo
+            .commit(None, &sig, &sig, "commit", &tree, &parent_refs)
+            .unwrap();
+        repo.reference(refname, oid, true, "test").unwrap();
+        oid
+    }
+
+    #[test]
+    fn test_uncommitted_changes_block_switch_until_stashed() {
+        let (dir, repo) = init_repo();
+        repo.config().unwrap().set_str("user.name", "test").unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("user.email", "test@example.com")
+            .unwrap();
+        let base = commit_file(&repo, "refs/heads/main", &[("a.txt", "main")], None);
+        commit_file(
+            &repo,
+            "refs/heads/feature",
+            &[("a.txt", "feature")],
+            Some(base),
+        );
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+
+        std::fs::write(dir.path().join("untracked.txt"), "new").unwrap();
+        assert!(!has_uncommitted_changes(&repo).unwrap());
+
+        std::fs::write(dir.path().join("a.txt"), "edited").unwrap();
+        assert!(has_uncommitted_changes(&repo).unwrap());
+        assert!(switch_branch(&repo, "feature").is_err());
+
+        stash_changes(&repo, "test").unwrap();
+        assert!(!has_uncommitted_changes(&repo).unwrap());
+        switch_branch(&repo, "feature").unwrap();
+        assert_eq!(get_current_branch(&repo).unwrap(), "feature");
+    }
+
+    #[test]
+    fn test_switch_branch_rolls_back_when_set_head_fails() {
+        let (dir, repo) = init_repo();
+        let base = commit_file(
+            &repo,
+            "refs/heads/main",
+            &[("a.txt", "main"), ("b.txt", "keep")],
+            None,
+        );
+        commit_file(
+            &repo,
+            "refs/heads/feature",
+            &[("a.txt", "feature"), ("c.txt", "new")],
+            Some(base),
+        );
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let result = switch_branch_with(&repo, "feature", false, |_, _| {
+            Err(git2::Error::from_str("simulated failure"))
+        });
+
+        match result {
+            Err(Error::SwitchBranch { step, .. }) => assert_eq!(step, SwitchStep::SetHead),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(get_current_branch(&repo).unwrap(), "main");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "main"
+        );
+        assert!(dir.path().join("b.txt").exists());
+        assert!(!dir.path().join("c.txt").exists());
+        assert!(repo.statuses(None).unwrap().is_empty());
+
+        switch_branch(&repo, "feature").unwrap();
+        assert_eq!(get_current_branch(&repo).unwrap(), "feature");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "feature"
+        );
+    }
+
+    #[test]
+    fn test_is_branch_merged_direction() {
+        let (_dir, repo) = init_repo();
+        let base = commit_file(&repo, "refs/heads/main", &[("a.txt", "a")], None);
+
+        // Same tip as the base counts as merged
+        repo.reference("refs/heads/same", base, true, "test")
+            .unwrap();
+        assert!(is_branch_merged(&repo, "same", "main").unwrap());
+
+        // A branch ahead of the base is not merged...
+        let ahead = commit_file(
+            &repo,
+            "refs/heads/feature",
+            &[("a.txt", "a"), ("b.txt", "b")],
+            Some(base),
+        );
+        assert!(!is_branch_merged(&repo, "feature", "main").unwrap());
+
+        // ...until the base moves past it, while the base itself is not merged into the branch
+        commit_file(
+            &repo,
+            "refs/heads/main",
+            &[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")],
+            Some(ahead),
+        );
+        assert!(is_branch_merged(&repo, "feature", "main").unwrap());
+        assert!(!is_branch_merged(&repo, "main", "feature").unwrap());
+    }
+
+    #[test]
+    fn test_get_version_tags() {
+        let (_dir, repo) = init_repo();
+        let mut parent = Vec::new();
+        for tag in [
+            "v1.2.0",
+            "v1.9.0",
+            "v1.10.0",
+            "latest",
+            "v2.0.0-rc.1",
+            "v2.0.0",
+        ] {
+            let oid = commit(&repo, "refs/heads/main", &parent);
+            repo.reference(&format!("refs/tags/{}", tag), oid, true, "test")
+                .unwrap();
+            parent = vec![oid];
+        }
+        // Tags off the current history are skipped
+        let side = commit(&repo, "refs/heads/side", &parent);
+        repo.reference("refs/tags/v3.0.0", side, true, "test")
+            .unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        assert_eq!(
+            get_version_tags(&repo).unwrap(),
+            vec!["v1.2.0", "v1.9.0", "v1.10.0", "v2.0.0-rc.1", "v2.0.0"]
+        );
+    }
+
+    #[test]
+    fn test_recent_branches_skip_missing_and_detached() {
+        let (_dir, repo) = init_repo();
+        let base = commit(&repo, "refs/heads/main", &[]);
+        repo.reference("refs/heads/feature", base, true, "test")
+            .unwrap();
+
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        let mut reflog = repo.reflog("HEAD").unwrap();
+        for message in [
+            "checkout: moving from main to feature",
+            "checkout: moving from feature to deleted",
+            "checkout: moving from deleted to main",
+            &format!("checkout: moving from main to {}", base),
+        ] {
+            reflog.append(base, &sig, Some(message)).unwrap();
+        }
+        reflog.write().unwrap();
+
+        // Newest first: the detached commit and the deleted branch are skipped
+        assert_eq!(
+            get_recent_branches(&repo, None).unwrap(),
+            vec!["main", "feature"]
+        );
+        assert_eq!(get_recent_branches(&repo, Some(1)).unwrap(), vec!["main"]);
+    }
+
+    #[test]
+    fn test_cherry_merged_branch() {
+        let (_dir, repo) = init_repo();
+        let base = commit_file(&repo, "refs/heads/main", &[("a.txt", "a")], None);
+        commit_file(
+            &repo,
+            "refs/heads/feature",
+            &[("a.txt", "a"), ("b.txt", "b")],
+            Some(base),
+        );
+        // The same change applied to main on top of another commit (as a rebase would)
+        let other = commit_file(
+            &repo,
+            "refs/heads/main",
+            &[("a.txt", "a"), ("c.txt", "c")],
+            Some(base),
+        );
+        commit_file(
+            &repo,
+            "refs/heads/main",
+            &[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")],
+            Some(other),
+        );
+
+        assert!(!is_branch_merged(&repo, "feature", "main").unwrap());
+        assert!(is_branch_cherry_merged(&repo, "feature", "main").unwrap());
+        assert!(is_branch_merged_with(&repo, "feature", "main", MergeStrategy::Both).unwrap());
+        assert!(!is_branch_merged_with(&repo, "feature", "main", MergeStrategy::Ancestor).unwrap());
+    }
+
+    #[test]
+    fn test_find_worktree_for_branch() {
+        let (dir, repo) = init_repo();
+        let base = commit(&repo, "refs/heads/main", &[]);
+        commit(&repo, "refs/heads/feature", &[base]);
+        repo.set_head("refs/heads/main").unwrap();
+
+        let wt_path = dir.path().join("wt");
+        let feature = repo.find_reference("refs/heads/feature").unwrap();
+        let mut options = git2::WorktreeAddOptions::new();
+        options.reference(Some(&feature));
+        let worktree = repo.worktree("wt", &wt_path, Some(&options)).unwrap();
+        let wt_repo = Repository::open_from_worktree(&worktree).unwrap();
+
+        let found = |repo: &Repository, branch: &str| {
+            find_worktree_for_branch(repo, branch)
+                .unwrap()
+                .map(|path| path.canonicalize().unwrap())
+        };
+        assert_eq!(
+            found(&repo, "feature"),
+            Some(wt_path.canonicalize().unwrap())
+        );
+        assert_eq!(
+            found(&wt_repo, "main"),
+            Some(dir.path().canonicalize().unwrap())
+        );
+        // The worktree the repository was opened in is never reported
+        assert_eq!(found(&repo, "main"), None);
+        assert_eq!(found(&wt_repo, "feature"), None);
+    }
+
+    #[test]
+    fn test_is_upstream_gone() {
+        let (_dir, repo) = init_repo();
+        let base = commit(&repo, "refs/heads/main", &[]);
+        commit(&repo, "refs/heads/feature", &[base]);
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("branch.feature.remote", "origin").unwrap();
+        config
+            .set_str("branch.feature.merge", "refs/heads/feature")
+            .unwrap();
+
+        assert!(is_upstream_gone(&repo, "feature").unwrap());
+        assert!(!is_upstream_gone(&repo, "main").unwrap());
+
+        repo.reference("refs/remotes/origin/feature", base, true, "test")
+            .unwrap();
+        assert!(!is_upstream_gone(&repo, "feature").unwrap());
+    }
+
+    #[test]
+    fn test_prune_remote() {
+        let remote_dir = TempDir::new().unwrap();
+        let remote_repo = Repository::init_bare(remote_dir.path()).unwrap();
+        commit(&remote_repo, "refs/heads/main", &[]);
+
+        let (_dir, repo) = init_repo();
+        repo.remote("origin", remote_dir.path().to_str().unwrap())
+            .unwrap();
+        let local = commit(&repo, "refs/heads/main", &[]);
+        for name in ["main", "merged"] {
+            repo.reference(
+                &format!("refs/remotes/origin/{}", name),
+                local,
+                true,
+                "test",
+            )
+            .unwrap();
+        }
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+            true,
+            "test",
+        )
+        .unwrap();
+
+        assert_eq!(
+            prune_remote(&repo, "origin", true).unwrap(),
+            vec!["origin/merged"]
+        );
+        assert!(repo.find_reference("refs/remotes/origin/merged").is_ok());
+
+        assert_eq!(
+            prune_remote(&repo, "origin", false).unwrap(),
+            vec!["origin/merged"]
+        );
+        assert!(repo.find_reference("refs/remotes/origin/merged").is_err());
+        assert!(repo.find_reference("refs/remotes/origin/main").is_ok());
+        assert!(repo.find_reference("refs/remotes/origin/HEAD").is_ok());
+    }
+
+    #[test]
+    fn test_squash_merged_branch() {
+        let (_dir, repo) = init_repo();
+        let base = commit_file(&repo, "refs/heads/main", &[("a.txt", "a")], None);
+        let first = commit_file(
+            &repo,
+            "refs/heads/feature",
+            &[("a.txt", "a"), ("b.txt", "b1")],
+            Some(base),
+        );
+        commit_file(
+            &repo,
+            "refs/heads/feature",
+            &[("a.txt", "a"), ("b.txt", "b2")],
+            Some(first),
+        );
+        commit_file(
+            &repo,
+            "refs/heads/wip",
+            &[("a.txt", "a"), ("b.txt", "b2"), ("d.txt", "d")],
+            Some(first),
+        );
+        // Both feature commits squashed into one on main, after another change
+        let other = commit_file(
+            &repo,
+            "refs/heads/main",
+            &[("a.txt", "a"), ("c.txt", "c")],
+            Some(base),
+        );
+        commit_file(
+            &repo,
+            "refs/heads/main",
+            &[("a.txt", "a"), ("b.txt", "b2"), ("c.txt", "c")],
+            Some(other),
+        );
+
+        assert!(!is_branch_cherry_merged(&repo, "feature", "main").unwrap());
+        assert!(is_branch_squash_merged(&repo, "feature", "main").unwrap());
+        assert_eq!(
+            branch_merge_status(&repo, "feature", "main", MergeStrategy::Both).unwrap(),
+            MergeStatus::Squashed
+        );
+        // More work on top of the squashed change
+        assert!(!is_branch_squash_merged(&repo, "wip", "main").unwrap());
+    }
 }
@@ -1,4 +1,7 @@
+pub mod auth;
+pub mod config;
 pub mod error;
 pub mod git;
+pub mod term;
 
-pub use error::{Error, Result};
+pub use error::{Error, Result, SwitchStep};
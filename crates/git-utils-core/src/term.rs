@@ -0,0 +1,199 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+
+/// Decide whether JSON output should be pretty-printed
+///
+/// Explicit `--pretty`/`--compact` flags win; otherwise pretty output is used
+/// when stdout is a terminal and compact output when it is piped.
+pub fn use_pretty_json(pretty: bool, compact: bool) -> bool {
+    if pretty {
+        true
+    } else if compact {
+        false
+    } else {
+        std::io::stdout().is_terminal()
+    }
+}
+
+/// When to use colors and other terminal styling (including OSC 8 hyperlinks)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Style output only when stdout is a terminal
+    #[default]
+    Auto,
+    /// Always style output
+    Always,
+    /// Never style output
+    Never,
+}
+
+/// Decide whether styled output should be used
+///
+/// In `auto` mode, `NO_COLOR` disables styling and `CLICOLOR_FORCE` forces it;
+/// otherwise styling is used only when stdout is a terminal.
+pub fn use_color(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+                false
+            } else if std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0")
+            {
+                true
+            } else {
+                std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Decide whether OSC 8 hyperlinks should be emitted
+///
+/// Follows [`use_color`], except that in `auto` mode `NO_HYPERLINKS` and
+/// `TERM=dumb` turn links off even when colors are on.
+pub fn use_hyperlinks(choice: ColorChoice) -> bool {
+    if choice == ColorChoice::Auto
+        && (std::env::var_os("NO_HYPERLINKS").is_some_and(|v| !v.is_empty())
+            || std::env::var_os("TERM").is_some_and(|v| v == "dumb"))
+    {
+        return false;
+    }
+    use_color(choice)
+}
+
+/// Wrap `text` in an OSC 8 hyperlink to `url`
+///
+/// Terminals without OSC 8 support show just the text. Check
+/// [`use_hyperlinks`] before using this for output that may not be a terminal.
+pub fn osc8_link(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Exit code used when the user cancels an interactive prompt (as for SIGINT)
+pub const CANCELLED_EXIT_CODE: u8 = 130;
+
+/// Whether an error (or any error in its source chain) is a cancelled prompt
+///
+/// Covers Esc (`OperationCanceled`), Ctrl-C (`OperationInterrupted`) and stdin
+/// reaching EOF mid-prompt.
+pub fn is_prompt_cancelled(err: &(dyn std::error::Error + 'static)) -> bool {
+    use inquire::InquireError;
+
+    let mut current = Some(err);
+    while let Some(e) = current {
+        if let Some(inquire_err) = e.downcast_ref::<InquireError>() {
+            return match inquire_err {
+                InquireError::OperationCanceled | InquireError::OperationInterrupted => true,
+                InquireError::IO(io_err) => io_err.kind() == std::io::ErrorKind::UnexpectedEof,
+                _ => false,
+            };
+        }
+        current = e.source();
+    }
+    false
+}
+
+/// Exit code for a binary's `run()` result, printing the error to stderr
+///
+/// A cancelled prompt prints "Cancelled" and exits with
+/// [`CANCELLED_EXIT_CODE`]; any other error is printed with its context chain.
+pub fn exit_code<E>(result: Result<(), E>) -> ExitCode
+where
+    E: std::fmt::Debug + AsRef<dyn std::error::Error + 'static>,
+{
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_prompt_cancelled(e.as_ref()) => {
+            eprintln!("Cancelled");
+            ExitCode::from(CANCELLED_EXIT_CODE)
+        }
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Format an age in seconds as a rough relative time, e.g. "3 days ago"
+pub fn format_age(seconds: i64) -> String {
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 24 * 3600, "year"),
+        (30 * 24 * 3600, "month"),
+        (7 * 24 * 3600, "week"),
+        (24 * 3600, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+
+    for (unit_seconds, name) in UNITS {
+        let count = seconds / unit_seconds;
+        if count > 0 {
+            return format!(
+                "{} {}{} ago",
+                count,
+                name,
+                if count == 1 { "" } else { "s" }
+            );
+        }
+    }
+    "just now".to_string()
+}
+
+/// Keep items whose key fuzzy-matches `pattern`, ordered by descending score
+///
+/// Equal scores keep their existing order (so an earlier sort still breaks
+/// ties), and an empty pattern keeps every item untouched.
+pub fn rank_by_pattern<T>(items: &mut Vec<T>, pattern: &str, key: impl Fn(&T) -> &str) {
+    if pattern.is_empty() {
+        return;
+    }
+
+    let matcher = SkimMatcherV2::default();
+    let mut scored: Vec<(i64, T)> = items
+        .drain(..)
+        .filter_map(|item| Some((matcher.fuzzy_match(key(&item), pattern)?, item)))
+        .collect();
+    scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+    items.extend(scored.into_iter().map(|(_, item)| item));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc8_link() {
+        assert_eq!(
+            osc8_link("https://example.com/pull/1", "#1"),
+            "\x1b]8;;https://example.com/pull/1\x1b\\#1\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(30), "just now");
+        assert_eq!(format_age(3600), "1 hour ago");
+        assert_eq!(format_age(3 * 24 * 3600), "3 days ago");
+    }
+
+    #[test]
+    fn test_rank_by_pattern() {
+        let mut branches = vec![
+            "main".to_string(),
+            "release/1.0".to_string(),
+            "feature/remote-name".to_string(),
+        ];
+        rank_by_pattern(&mut branches, "frmn", |b| b);
+        assert_eq!(branches, vec!["feature/remote-name"]);
+
+        let mut branches = vec!["fix/remote".to_string(), "remote".to_string()];
+        rank_by_pattern(&mut branches, "remote", |b| b);
+        assert_eq!(branches, vec!["remote", "fix/remote"]);
+
+        rank_by_pattern(&mut branches, "", |b| b);
+        assert_eq!(branches.len(), 2);
+    }
+}
@@ -0,0 +1,15 @@
+use anyhow::Result;
+use clap::Args;
+use git_utils_core::git;
+
+#[derive(Args)]
+pub struct BaseBranch {}
+
+impl BaseBranch {
+    pub fn execute(&self) -> Result<()> {
+        let repo = git::open_repo()?;
+        let base_branch = git::detect_base_branch(&repo)?;
+        println!("{}", base_branch);
+        Ok(())
+    }
+}
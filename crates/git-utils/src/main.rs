@@ -1,12 +1,16 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 
+mod base_branch;
 mod setup;
+mod version;
 
+use base_branch::BaseBranch;
 use setup::Setup;
+use version::Version;
 
 #[derive(Parser)]
-#[command(name = "git-utils")]
+#[command(name = "git-utils", version)]
 #[command(about = "Git utilities setup and management", long_about = None)]
 struct Cli {
     #[command(subcommand)]
@@ -17,6 +21,12 @@ struct Cli {
 enum Commands {
     /// Setup git-utils environment
     Setup(Setup),
+
+    /// Print the base branch detected for the current repository
+    BaseBranch(BaseBranch),
+
+    /// Print versions of the git-utils tools and the external tools they use
+    Version(Version),
 }
 
 fn main() -> Result<()> {
@@ -24,6 +34,8 @@ fn main() -> Result<()> {
 
     match cli.command {
         Commands::Setup(setup) => setup.execute()?,
+        Commands::BaseBranch(base_branch) => base_branch.execute()?,
+        Commands::Version(version) => version.execute()?,
     }
 
     Ok(())
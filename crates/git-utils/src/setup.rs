@@ -2,7 +2,8 @@ use anyhow::{anyhow, Result};
 use clap::{Args, ValueEnum};
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum Shell {
@@ -38,6 +39,10 @@ pub struct Setup {
     /// Uninstall git-utils setup
     #[arg(long)]
     uninstall: bool,
+
+    /// Overwrite existing env files with the current templates (backed up to .bak)
+    #[arg(long, conflicts_with_all = ["print", "gitconfig", "uninstall"])]
+    force: bool,
 }
 
 const ENV_SH_TEMPLATE: &str = r#"# git-utils environment setup (bash/zsh)
@@ -131,22 +136,26 @@ impl Setup {
         println!("  {}", env_sh_example.display());
         println!("  {}", env_fish_example.display());
 
-        // Only create actual env files on first install
+        // Only create actual env files on first install, unless --force
         let mut created_files: Vec<PathBuf> = Vec::new();
         let mut existing_files: Vec<PathBuf> = Vec::new();
-
-        if !env_sh.exists() {
-            fs::write(&env_sh, ENV_SH_TEMPLATE)?;
-            created_files.push(env_sh.clone());
-        } else {
-            existing_files.push(env_sh.clone());
-        }
-
-        if !env_fish.exists() {
-            fs::write(&env_fish, ENV_FISH_TEMPLATE)?;
-            created_files.push(env_fish.clone());
-        } else {
-            existing_files.push(env_fish.clone());
+        let mut overwritten_files: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+        for (file, template) in [(&env_sh, ENV_SH_TEMPLATE), (&env_fish, ENV_FISH_TEMPLATE)] {
+            if !file.exists() {
+                fs::write(file, template)?;
+                created_files.push(file.clone());
+            } else if self.force {
+                if fs::read_to_string(file).is_ok_and(|content| content == template) {
+                    continue;
+                }
+                let backup = backup_path(file);
+                fs::copy(file, &backup)?;
+                fs::write(file, template)?;
+                overwritten_files.push((file.clone(), backup));
+            } else {
+                existing_files.push(file.clone());
+            }
         }
 
         if !created_files.is_empty() {
@@ -156,12 +165,22 @@ impl Setup {
             }
         }
 
+        if !overwritten_files.is_empty() {
+            println!("\nOverwrote environment files with the current templates:");
+            for (file, backup) in &overwritten_files {
+                println!("  {} (backup: {})", file.display(), backup.display());
+            }
+        }
+
         if !existing_files.is_empty() {
             println!("\nExisting files preserved (not overwritten):");
             for file in &existing_files {
                 println!("  {}", file.display());
             }
-            println!("\nTo update your env files with new templates, compare with .example files:");
+            println!("\nTo update your env files with new templates, compare with .example files");
+            println!(
+                "(or run `git-utils setup --force` to overwrite them, keeping a .bak backup):"
+            );
             println!(
                 "  git diff --no-index {} {}",
                 env_sh.display(),
@@ -184,30 +203,21 @@ impl Setup {
         self.add_source_line(shell)?;
 
         println!("\nSetup complete!");
-        let rc_path = match shell {
-            Shell::Fish => "config/fish/config.fish".to_string(),
-            Shell::Bash => "bashrc".to_string(),
-            Shell::Zsh => "zshrc".to_string(),
-        };
-        println!("Please restart your shell or run: source ~/.{}", rc_path);
+        println!(
+            "Please restart your shell or run: source {}",
+            Self::get_rc_file(shell)?.display()
+        );
 
         Ok(())
     }
 
     fn add_source_line(&self, shell: Shell) -> Result<()> {
-        let (rc_file, source_line) = match shell {
-            Shell::Bash => (
-                Self::get_home_dir()?.join(".bashrc"),
-                "[ -f ~/.git-utils/env.sh ] && source ~/.git-utils/env.sh\n",
-            ),
-            Shell::Zsh => (
-                Self::get_home_dir()?.join(".zshrc"),
-                "[ -f ~/.git-utils/env.sh ] && source ~/.git-utils/env.sh\n",
-            ),
-            Shell::Fish => (
-                Self::get_home_dir()?.join(".config/fish/config.fish"),
-                "test -f ~/.git-utils/env.fish && source ~/.git-utils/env.fish\n",
-            ),
+        let rc_file = Self::get_rc_file(shell)?;
+        let source_line = match shell {
+            Shell::Bash | Shell::Zsh => {
+                "[ -f ~/.git-utils/env.sh ] && source ~/.git-utils/env.sh\n"
+            }
+            Shell::Fish => "test -f ~/.git-utils/env.fish && source ~/.git-utils/env.fish\n",
         };
 
         // Create parent directory for fish config if needed
@@ -250,7 +260,10 @@ impl Setup {
                 println!("[ -f ~/.git-utils/env.sh ] && source ~/.git-utils/env.sh");
             }
             Shell::Fish => {
-                println!("# Add this to your ~/.config/fish/config.fish:");
+                println!(
+                    "# Add this to your {}:",
+                    Self::get_rc_file(shell)?.display()
+                );
                 println!("test -f ~/.git-utils/env.fish && source ~/.git-utils/env.fish");
             }
         }
@@ -262,11 +275,7 @@ impl Setup {
 
         // Remove source lines from rc files
         for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
-            let rc_file = match shell {
-                Shell::Bash => Self::get_home_dir()?.join(".bashrc"),
-                Shell::Zsh => Self::get_home_dir()?.join(".zshrc"),
-                Shell::Fish => Self::get_home_dir()?.join(".config/fish/config.fish"),
-            };
+            let rc_file = Self::get_rc_file(shell)?;
 
             if rc_file.exists() {
                 Self::remove_source_lines(&rc_file)?;
@@ -331,7 +340,49 @@ impl Setup {
         dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))
     }
 
+    /// Shell startup file the source line goes into
+    ///
+    /// fish reads its config from `$XDG_CONFIG_HOME/fish/config.fish`, falling
+    /// back to `~/.config/fish/config.fish` when the variable is unset.
+    fn get_rc_file(shell: Shell) -> Result<PathBuf> {
+        let home = Self::get_home_dir()?;
+        Ok(match shell {
+            Shell::Bash => home.join(".bashrc"),
+            Shell::Zsh => home.join(".zshrc"),
+            Shell::Fish => {
+                let config_home = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .filter(|p| p.is_absolute())
+                    .unwrap_or_else(|| home.join(".config"));
+                config_home.join("fish").join("config.fish")
+            }
+        })
+    }
+
     fn get_git_utils_dir() -> Result<PathBuf> {
         Ok(Self::get_home_dir()?.join(".git-utils"))
     }
 }
+
+/// Where to back up `file` before overwriting it
+///
+/// Uses `<file>.bak`, unless that already holds a different earlier backup;
+/// then the new backup gets a timestamp so the old one is kept.
+fn backup_path(file: &Path) -> PathBuf {
+    let backup = PathBuf::from(format!("{}.bak", file.display()));
+    let reusable = match (fs::read(&backup), fs::read(file)) {
+        (Ok(old), Ok(current)) => old == current,
+        (Err(_), _) => !backup.exists(),
+        _ => false,
+    };
+    if reusable {
+        return backup;
+    }
+
+    let stamp: String = humantime::format_rfc3339_seconds(SystemTime::now())
+        .to_string()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    PathBuf::from(format!("{}.bak.{}", file.display(), stamp))
+}
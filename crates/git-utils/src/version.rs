@@ -0,0 +1,52 @@
+use anyhow::Result;
+use clap::Args;
+use std::process::Command;
+
+/// Binaries shipped with git-utils
+const SUITE_TOOLS: &[&str] = &[
+    "git-branch-switch",
+    "git-branch-delete",
+    "git-repos",
+    "git-pr-merged",
+];
+
+/// External tools the suite relies on
+const EXTERNAL_TOOLS: &[&str] = &["git", "gh", "glab", "fzf"];
+
+#[derive(Args)]
+pub struct Version {}
+
+impl Version {
+    pub fn execute(&self) -> Result<()> {
+        println!("git-utils {}", env!("CARGO_PKG_VERSION"));
+
+        for tool in SUITE_TOOLS {
+            match tool_version(tool) {
+                Some(version) => println!("{}", version),
+                None => println!("{} (not found)", tool),
+            }
+        }
+
+        println!();
+        for tool in EXTERNAL_TOOLS {
+            match tool_version(tool) {
+                Some(version) => println!("{}: {}", tool, version),
+                None => println!("{}: not found", tool),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Run `<tool> --version` and return the first line of its output
+fn tool_version(tool: &str) -> Option<String> {
+    let output = Command::new(tool).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}